@@ -0,0 +1,275 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use ids_parser::{
+    ComponentFilter, DecomposeOptions, IdsDatabase, IdsQuery, LoadMode, Origin, ParseError,
+    RegionPreference,
+};
+use serde::Serialize;
+
+/// Queries IDS data of a babelstone IDS.TXT file
+#[derive(Parser)]
+#[command(name = "ids", version)]
+struct Cli {
+    /// The IDS file to load
+    #[arg(short, long, env = "IDS_FILE", default_value = "IDS.TXT")]
+    file: PathBuf,
+
+    /// Print the output as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prints the compositions of a character
+    Decompose {
+        literal: char,
+        /// Only use the composition of this origin, eg. 'J' or 'KP'. Components without one stay
+        /// unexpanded with --recursive
+        #[arg(short, long, value_parser = parse_origin, conflicts_with_all = ["prefer", "locale"])]
+        origin: Option<Origin>,
+        /// Select the composition by an ordered list of origins, eg. "J,T,H"
+        #[arg(short, long, conflicts_with = "locale")]
+        prefer: Option<RegionPreference>,
+        /// Select the composition by the preference of a locale, eg. "ja" or "zh-Hant"
+        #[arg(short, long, value_parser = parse_locale)]
+        locale: Option<RegionPreference>,
+        /// Expand all components until only atomic ones are left
+        #[arg(short, long)]
+        recursive: bool,
+        /// Maximum amount of levels to expand
+        #[arg(long, requires = "recursive")]
+        depth: Option<usize>,
+    },
+    /// Prints all characters containing a component
+    Contains {
+        component: char,
+        /// Only take compositions of this origin into account
+        #[arg(short, long, value_parser = parse_origin)]
+        origin: Option<Origin>,
+        /// Also match components nested within inner forms
+        #[arg(short, long)]
+        nested: bool,
+    },
+    /// Prints all characters matching a pattern like "⿰氵?"
+    Search { pattern: String },
+    /// Prints component frequencies and other statistics of the IDS file
+    Stats {
+        /// Amount of most frequent components to print
+        #[arg(long, default_value_t = 50)]
+        top: usize,
+    },
+    /// Checks every line of an IDS file and prints all errors
+    Validate { file: PathBuf },
+    /// Converts the IDS file into another format
+    Convert {
+        #[arg(long, value_enum)]
+        to: Format,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    /// The compact binary format, see `ids_parser::binary`
+    Binary,
+}
+
+/// A single invalid line reported by `validate`
+#[derive(Serialize)]
+struct Problem {
+    line: Option<usize>,
+    column: Option<usize>,
+    message: String,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: &Cli) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    // Validating loads its own file
+    let load = || IdsDatabase::from_path(&cli.file, LoadMode::Lenient);
+
+    match &cli.command {
+        Command::Validate { file } => return validate(file, cli.json),
+        Command::Decompose {
+            literal,
+            origin,
+            prefer,
+            locale,
+            recursive,
+            depth,
+        } => {
+            let db = load()?;
+            let Some(ids) = db.get(*literal) else {
+                return Err(format!("no entry for {literal}").into());
+            };
+            // An explicit origin never falls back to a composition of another one
+            let strict = origin.map(|i| RegionPreference::from(i).without_fallback());
+            let preference = prefer.or(*locale).or(strict);
+
+            if *recursive {
+                let options = DecomposeOptions {
+                    preference: preference.unwrap_or_default(),
+                    max_depth: *depth,
+                };
+                let Some(tree) = db.decompose(*literal, options) else {
+                    let preference = options.preference;
+                    return Err(format!("no composition of {literal} for {preference}").into());
+                };
+                let components = db.decompose_components(*literal, options);
+                if cli.json {
+                    let out = serde_json::json!({ "tree": tree, "components": components });
+                    println!("{}", serde_json::to_string_pretty(&out)?);
+                } else {
+                    println!("{tree}");
+                    println!("{}", components.iter().collect::<String>());
+                }
+                return Ok(ExitCode::SUCCESS);
+            }
+
+            if let (None, Some(preference)) = (origin, preference) {
+                let Some(selection) = ids.select(&preference) else {
+                    return Err(format!("no composition of {literal} for {preference}").into());
+                };
+                if cli.json {
+                    println!("{}", serde_json::to_string_pretty(&selection)?);
+                } else {
+                    println!("{}\t{}", selection.composition, selection.reason);
+                }
+                return Ok(ExitCode::SUCCESS);
+            }
+
+            let compositions: Vec<_> = match origin {
+                Some(origin) => {
+                    let Some(composition) = ids.comp_by_lang(*origin) else {
+                        let origin = origin.code();
+                        return Err(format!("no composition of {literal} for {origin}").into());
+                    };
+                    vec![composition]
+                }
+                None => ids.compositions.iter().collect(),
+            };
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&compositions)?);
+            } else {
+                for composition in compositions {
+                    println!("{composition}");
+                }
+            }
+        }
+        Command::Contains {
+            component,
+            origin,
+            nested,
+        } => {
+            let db = load()?;
+            let filter = ComponentFilter {
+                origin: *origin,
+                nested: *nested,
+            };
+            let found = db.component_index().find(*component, filter);
+            print_chars(&found, cli.json)?;
+        }
+        Command::Search { pattern } => {
+            let query = IdsQuery::from_str(pattern)?;
+            let db = load()?;
+            let found: Vec<char> = db.search(&query).iter().map(|i| i.literal).collect();
+            print_chars(&found, cli.json)?;
+        }
+        Command::Stats { top } => {
+            let db = load()?;
+            let stats = db.statistics().truncated(*top);
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                print!("{stats}");
+            }
+        }
+        Command::Convert { to: Format::Json } => {
+            let db = load()?;
+            let entries: Vec<_> = db.iter().collect();
+            println!("{}", serde_json::to_string(&entries)?);
+        }
+        Command::Convert { to: Format::Binary } => {
+            let db = load()?;
+            ids_parser::binary::write_database(&db, std::io::stdout().lock())?;
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Loads `file` leniently and prints all errors and warnings. Fails if there is any error.
+fn validate(file: &Path, json: bool) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let db = IdsDatabase::from_path(file, LoadMode::Lenient)?;
+
+    let to_problem = |err: &ParseError| Problem {
+        line: err.line,
+        column: err.column,
+        message: err.kind.to_string(),
+    };
+    let errors: Vec<Problem> = db.errors().iter().map(to_problem).collect();
+    let warnings: Vec<Problem> = db.warnings().iter().map(to_problem).collect();
+
+    if json {
+        let out = serde_json::json!({
+            "entries": db.len(),
+            "errors": errors,
+            "warnings": warnings,
+        });
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        for err in db.errors() {
+            println!("error: {err}");
+        }
+        for warning in db.warnings() {
+            println!("warning: {warning}");
+        }
+        println!(
+            "{} entries, {} errors, {} warnings",
+            db.len(),
+            errors.len(),
+            warnings.len()
+        );
+    }
+
+    Ok(if errors.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn print_chars(chars: &[char], json: bool) -> Result<(), serde_json::Error> {
+    if json {
+        println!("{}", serde_json::to_string(chars)?);
+    } else {
+        println!("{}", chars.iter().collect::<String>());
+    }
+    Ok(())
+}
+
+fn parse_origin(s: &str) -> Result<Origin, ParseError> {
+    Origin::from_str(s)
+}
+
+fn parse_locale(s: &str) -> Result<RegionPreference, String> {
+    RegionPreference::from_locale(s).ok_or_else(|| format!("unsupported locale {s:?}"))
+}
@@ -0,0 +1,476 @@
+//! Compact binary format of a whole `IdsDatabase`.
+//!
+//! All integers are little endian. The file starts with a header:
+//!
+//! | Size | Content                                                    |
+//! |------|------------------------------------------------------------|
+//! | 4    | Magic bytes "IDSB"                                         |
+//! | 2    | Format version                                             |
+//! | 2    | Reserved, always 0                                         |
+//! | 4    | Amount of interned components                              |
+//! | 4    | Amount of entries                                          |
+//! | 4    | Amount of lookup items                                     |
+//!
+//! It is followed by the component table (one `u32` code point per component), the entry table
+//! (`u32` literal and `u32` offset of the entry data per entry, in load order), the lookup table
+//! (`u32` literal and `u32` entry index, sorted by literal, for entries without a sequence) and
+//! the data of all entries. Counts and numbers within the entry data are LEB128 encoded, radicals
+//! are indices into the component table and origins are bitsets.
+
+use std::{collections::HashMap, io::Write};
+
+use crate::{
+    composition::{CompositionPart, Modifier},
+    database::IdsDatabase,
+    destr_form::DestructionForm,
+    error::{ErrorKind, ParseError},
+    origin_set::OriginSet,
+    tree::IdsNode,
+    xref::{RefType, XRef, XRefItem, XRefLink},
+    Composition, Origin, IDS,
+};
+
+/// Magic bytes at the start of every blob
+pub const MAGIC: &[u8; 4] = b"IDSB";
+
+/// Version of the format written by `write_database`
+pub const FORMAT_VERSION: u16 = 1;
+
+const HEADER_LEN: usize = 20;
+
+/// Tags of tree nodes. Forms and modifiers get added to their base tag
+const TAG_FORM: u8 = 0x00;
+const TAG_MODIFIER: u8 = 0x40;
+const TAG_RADICAL: u8 = 0x80;
+const TAG_UNENCODED: u8 = 0x81;
+
+/// Marks a cross reference item without origin
+const NO_ORIGIN: u8 = 0xFF;
+
+/// Stable codes of all forms, modifiers and reference types. Origins are coded by their index
+/// in `Origin::ALL`
+const FORMS: &[DestructionForm] = &[
+    DestructionForm::Vertically,
+    DestructionForm::Horizontally,
+    DestructionForm::Vertically3,
+    DestructionForm::Horizontally3,
+    DestructionForm::BoxInner,
+    DestructionForm::BoxOpenBottom,
+    DestructionForm::BoxOpenTop,
+    DestructionForm::BoxOpenRight,
+    DestructionForm::BoxOpenLeft,
+    DestructionForm::BoxOpenBottomRight,
+    DestructionForm::BoxOpenBottomLeft,
+    DestructionForm::BoxOpenTopRight,
+    DestructionForm::BoxOpenTopLeft,
+    DestructionForm::Diagonal,
+    DestructionForm::Reflection,
+    DestructionForm::Rotation,
+    DestructionForm::Subtraction,
+];
+const MODIFIERS: &[Modifier] = &[
+    Modifier::UnrepresntableCompontent,
+    Modifier::IdeographicVariation,
+    Modifier::Mirror,
+    Modifier::Rotation,
+    Modifier::Subtraction,
+];
+const REF_TYPES: &[RefType] = &[
+    RefType::Full,
+    RefType::FullNoCognate,
+    RefType::UnifiableVariants,
+];
+
+/// Read only view of a binary blob. Entries get decoded on access, the tables get read in place.
+#[derive(Clone, Debug)]
+pub struct IdsBlob<'a> {
+    components: &'a [u8],
+    entries: &'a [u8],
+    lookup: &'a [u8],
+    data: &'a [u8],
+}
+
+impl<'a> IdsBlob<'a> {
+    /// Checks the header of `data` and the bounds of all tables
+    pub fn new(data: &'a [u8]) -> Result<Self, ParseError> {
+        let mut header = Reader::new(data);
+        if header.bytes(MAGIC.len())? != MAGIC {
+            return Err(ParseError::new(ErrorKind::InvalidMagic).at(0));
+        }
+        let version = header.u16()?;
+        if version != FORMAT_VERSION {
+            return Err(ParseError::new(ErrorKind::UnsupportedVersion(version)).at(4));
+        }
+        header.u16()?;
+        let component_count = header.u32()? as usize;
+        let entry_count = header.u32()? as usize;
+        let lookup_count = header.u32()? as usize;
+
+        let mut tables = Reader::new(data);
+        tables.pos = HEADER_LEN;
+        let components = tables.bytes(component_count * 4)?;
+        let entries = tables.bytes(entry_count * 8)?;
+        let lookup = tables.bytes(lookup_count * 8)?;
+
+        Ok(Self {
+            components,
+            entries,
+            lookup,
+            data: &data[tables.pos..],
+        })
+    }
+
+    /// Returns the entry of `literal`. Entries with a sequence are only available by index.
+    pub fn get(&self, literal: char) -> Option<IDS> {
+        let count = self.lookup.len() / 8;
+        let (mut low, mut high) = (0, count);
+        while low < high {
+            let mid = (low + high) / 2;
+            let c = read_u32(self.lookup, mid * 8);
+            match c.cmp(&(literal as u32)) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => {
+                    return self.entry(read_u32(self.lookup, mid * 8 + 4) as usize).ok();
+                }
+            }
+        }
+        None
+    }
+
+    /// Decodes the entry at `index` of the load order
+    pub fn entry(&self, index: usize) -> Result<IDS, ParseError> {
+        if index >= self.len() {
+            return Err(ParseError::new(ErrorKind::UnexpectedEnd).at(index * 8));
+        }
+        let literal = read_u32(self.entries, index * 8);
+        let literal = char::from_u32(literal)
+            .ok_or_else(|| ParseError::new(ErrorKind::InvalidBinary).at(index * 8))?;
+        let offset = read_u32(self.entries, index * 8 + 4) as usize;
+
+        let mut reader = Reader::new(self.data);
+        reader.pos = offset;
+        self.read_entry(&mut reader, literal)
+    }
+
+    /// Returns an iterator over all entries in load order. Invalid entries are returned as error.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = Result<IDS, ParseError>> + '_ {
+        (0..self.len()).map(|i| self.entry(i))
+    }
+
+    /// Decodes all entries into an `IdsDatabase`
+    #[inline]
+    pub fn to_database(&self) -> Result<IdsDatabase, ParseError> {
+        self.iter().collect()
+    }
+
+    /// Returns the amount of entries
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len() / 8
+    }
+
+    /// Returns `true` if there are no entries
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the interned component at `index`
+    fn component(&self, index: usize, at: usize) -> Result<char, ParseError> {
+        let err = || ParseError::new(ErrorKind::InvalidBinary).at(at);
+        if index * 4 >= self.components.len() {
+            return Err(err());
+        }
+        char::from_u32(read_u32(self.components, index * 4)).ok_or_else(err)
+    }
+
+    fn read_entry(&self, reader: &mut Reader, literal: char) -> Result<IDS, ParseError> {
+        let sequence = reader.opt_str()?;
+
+        let mut compositions = vec![];
+        for _ in 0..reader.varint()? {
+            compositions.push(self.read_composition(reader)?);
+        }
+
+        let mut xrefs = vec![];
+        for _ in 0..reader.varint()? {
+            let head = reader.xref_item()?;
+            let mut links = vec![];
+            for _ in 0..reader.varint()? {
+                let pos = reader.pos;
+                let ref_type = *REF_TYPES
+                    .get(reader.u8()? as usize)
+                    .ok_or_else(|| ParseError::new(ErrorKind::InvalidBinary).at(pos))?;
+                let item = reader.xref_item()?;
+                links.push(XRefLink { ref_type, item });
+            }
+            xrefs.push(XRef { head, links });
+        }
+
+        Ok(IDS {
+            code_point: literal as u32,
+            literal,
+            sequence,
+            compositions,
+            xrefs,
+        })
+    }
+
+    fn read_composition(&self, reader: &mut Reader) -> Result<Composition, ParseError> {
+        let start = reader.pos;
+        let mut parts = vec![];
+        for _ in 0..reader.varint()? {
+            let pos = reader.pos;
+            let err = || ParseError::new(ErrorKind::InvalidBinary).at(pos);
+            let part = match reader.u8()? {
+                TAG_RADICAL => {
+                    let index = reader.varint()? as usize;
+                    CompositionPart::Radical(self.component(index, pos)?)
+                }
+                TAG_UNENCODED => CompositionPart::UnencodedComponent(reader.varint()?),
+                tag if tag >= TAG_MODIFIER => CompositionPart::Modifier(
+                    *MODIFIERS
+                        .get((tag - TAG_MODIFIER) as usize)
+                        .ok_or_else(err)?,
+                ),
+                tag => CompositionPart::Destructive(
+                    *FORMS.get((tag - TAG_FORM) as usize).ok_or_else(err)?,
+                ),
+            };
+            parts.push(part);
+        }
+        let tree = IdsNode::from_parts(&parts).map_err(|e| ParseError::new(e.kind).at(start))?;
+
+        let reg_origins = OriginSet::from_bits(reader.u16()?);
+        let bracketed_origins = OriginSet::from_bits(reader.u16()?);
+        let note = reader.opt_str()?;
+
+        #[allow(deprecated)]
+        Ok(Composition {
+            reg_origins,
+            bracketed_origins,
+            note,
+            data: parts,
+            tree,
+        })
+    }
+}
+
+impl IdsDatabase {
+    /// Encodes the database in the compact binary format
+    #[inline]
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut out = vec![];
+        // Writing into a `Vec` can't fail
+        let _ = write_database(self, &mut out);
+        out
+    }
+
+    /// Decodes a database from the compact binary format
+    #[inline]
+    pub fn from_binary(data: &[u8]) -> Result<Self, ParseError> {
+        IdsBlob::new(data)?.to_database()
+    }
+}
+
+/// Writes all entries of `db` in the compact binary format
+pub fn write_database<W: Write>(db: &IdsDatabase, mut writer: W) -> std::io::Result<()> {
+    // Intern all radicals ordered by code point
+    let mut components: Vec<char> = db
+        .iter()
+        .flat_map(|i| i.compositions.iter())
+        .flat_map(|i| i.tree.leaves())
+        .filter_map(|i| i.as_radical().copied())
+        .collect();
+    components.sort_unstable();
+    components.dedup();
+    let component_ids: HashMap<char, u32> = components
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (*c, i as u32))
+        .collect();
+
+    let mut data = vec![];
+    let mut entries = vec![];
+    let mut lookup = vec![];
+    for (index, ids) in db.iter().enumerate() {
+        entries.push((ids.literal as u32, data.len() as u32));
+        if ids.sequence.is_none() {
+            lookup.push((ids.literal as u32, index as u32));
+        }
+        write_entry(&mut data, ids, &component_ids);
+    }
+    lookup.sort_unstable();
+
+    let mut out = Vec::with_capacity(HEADER_LEN + data.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out.extend_from_slice(&(components.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(lookup.len() as u32).to_le_bytes());
+    for c in &components {
+        out.extend_from_slice(&(*c as u32).to_le_bytes());
+    }
+    for (a, b) in entries.iter().chain(lookup.iter()) {
+        out.extend_from_slice(&a.to_le_bytes());
+        out.extend_from_slice(&b.to_le_bytes());
+    }
+    out.extend_from_slice(&data);
+
+    writer.write_all(&out)
+}
+
+fn write_entry(out: &mut Vec<u8>, ids: &IDS, component_ids: &HashMap<char, u32>) {
+    write_opt_str(out, ids.sequence.as_deref());
+
+    write_varint(out, ids.compositions.len() as u32);
+    for composition in &ids.compositions {
+        let parts = composition.data();
+        write_varint(out, parts.len() as u32);
+        for part in parts {
+            match part {
+                CompositionPart::Destructive(form) => out.push(TAG_FORM + code_of(FORMS, form)),
+                CompositionPart::Modifier(m) => out.push(TAG_MODIFIER + code_of(MODIFIERS, m)),
+                CompositionPart::Radical(r) => {
+                    out.push(TAG_RADICAL);
+                    write_varint(out, component_ids[&r]);
+                }
+                CompositionPart::UnencodedComponent(nr) => {
+                    out.push(TAG_UNENCODED);
+                    write_varint(out, nr);
+                }
+            }
+        }
+        out.extend_from_slice(&composition.reg_origins.bits().to_le_bytes());
+        out.extend_from_slice(&composition.bracketed_origins.bits().to_le_bytes());
+        write_opt_str(out, composition.note.as_deref());
+    }
+
+    write_varint(out, ids.xrefs.len() as u32);
+    for xref in &ids.xrefs {
+        write_xref_item(out, &xref.head);
+        write_varint(out, xref.links.len() as u32);
+        for link in &xref.links {
+            out.push(code_of(REF_TYPES, link.ref_type));
+            write_xref_item(out, &link.item);
+        }
+    }
+}
+
+fn write_xref_item(out: &mut Vec<u8>, item: &XRefItem) {
+    write_varint(out, item.literal as u32);
+    let origin = item.src_identifier.map(|i| i.index() as u8);
+    out.push(origin.unwrap_or(NO_ORIGIN));
+}
+
+/// Writes an optional string as its length + 1 followed by its bytes, or 0 for `None`
+fn write_opt_str(out: &mut Vec<u8>, s: Option<&str>) {
+    match s {
+        Some(s) => {
+            write_varint(out, s.len() as u32 + 1);
+            out.extend_from_slice(s.as_bytes());
+        }
+        None => write_varint(out, 0),
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[inline]
+fn code_of<T: PartialEq>(table: &[T], item: T) -> u8 {
+    table.iter().position(|i| *i == item).unwrap_or_default() as u8
+}
+
+#[inline]
+fn read_u32(data: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
+}
+
+/// Bounds checked reader over a byte slice. Errors point to the byte offset they occurred at
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    #[inline]
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], ParseError> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| ParseError::new(ErrorKind::UnexpectedEnd).at(self.pos))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    #[inline]
+    fn u8(&mut self) -> Result<u8, ParseError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    #[inline]
+    fn u16(&mut self) -> Result<u16, ParseError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    #[inline]
+    fn u32(&mut self) -> Result<u32, ParseError> {
+        Ok(read_u32(self.bytes(4)?, 0))
+    }
+
+    fn varint(&mut self) -> Result<u32, ParseError> {
+        let start = self.pos;
+        let mut value = 0u32;
+        for shift in (0..32).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7F) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ParseError::new(ErrorKind::InvalidBinary).at(start))
+    }
+
+    fn opt_str(&mut self) -> Result<Option<String>, ParseError> {
+        let len = match self.varint()? {
+            0 => return Ok(None),
+            len => len as usize - 1,
+        };
+        let start = self.pos;
+        let s = std::str::from_utf8(self.bytes(len)?)
+            .map_err(|_| ParseError::new(ErrorKind::InvalidBinary).at(start))?;
+        Ok(Some(s.to_string()))
+    }
+
+    fn xref_item(&mut self) -> Result<XRefItem, ParseError> {
+        let start = self.pos;
+        let err = || ParseError::new(ErrorKind::InvalidBinary).at(start);
+        let literal = char::from_u32(self.varint()?).ok_or_else(err)?;
+        let src_identifier = match self.u8()? {
+            NO_ORIGIN => None,
+            code => Some(*Origin::ALL.get(code as usize).ok_or_else(err)?),
+        };
+        Ok(XRefItem {
+            literal,
+            src_identifier,
+        })
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    composition::CompositionPart, database::IdsDatabase, map_special_form, origin_set::OriginSet,
+    pua_map::PuaMap, tree::IdsNode, utils, Origin,
+};
+
+/// Reverse index from components to all characters containing them
+#[derive(Default, Clone, Debug)]
+pub struct ComponentIndex {
+    /// Keyed by `component_key`
+    postings: HashMap<CompositionPart, Vec<Posting>>,
+    literals: Vec<char>,
+}
+
+/// A single occurrence of a component within a composition
+#[derive(Clone, Debug)]
+struct Posting {
+    literal: char,
+    origins: OriginSet,
+    /// Whether the component is a direct part of the top level `DestructionForm`
+    top_level: bool,
+}
+
+/// Restricts which occurrences of a component get taken into account
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ComponentFilter {
+    /// Only take compositions with this origin into account
+    pub origin: Option<Origin>,
+    /// Also match components nested within inner `DestructionForm`s
+    pub nested: bool,
+}
+
+/// A query for characters by their components
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct ComponentQuery {
+    /// Characters have to contain all of those components
+    pub all_of: Vec<char>,
+    /// Characters have to contain at least one of those components
+    pub any_of: Vec<char>,
+    /// Characters must not contain any of those components
+    pub none_of: Vec<char>,
+    pub filter: ComponentFilter,
+}
+
+impl ComponentIndex {
+    /// Builds the index over all entries of `db`
+    pub fn new(db: &IdsDatabase) -> Self {
+        let mut index = Self::default();
+
+        for ids in db.iter() {
+            index.literals.push(ids.literal);
+
+            for composition in &ids.compositions {
+                let mut components = HashMap::new();
+                collect_components(&composition.tree, 0, &mut components);
+
+                for (component, top_level) in components {
+                    // Atomic entries are composed of themselves
+                    if component == char_key(ids.literal) {
+                        continue;
+                    }
+
+                    index.postings.entry(component).or_default().push(Posting {
+                        literal: ids.literal,
+                        origins: composition.reg_origins,
+                        top_level,
+                    });
+                }
+            }
+        }
+
+        index
+    }
+
+    /// Returns all characters containing `component`, ordered by their code point. PUA
+    /// characters of the built in `PuaMap` find their unencoded component.
+    #[inline]
+    pub fn find(&self, component: char, filter: ComponentFilter) -> Vec<char> {
+        utils::sorted(self.find_set(char_key(component), filter))
+    }
+
+    /// Returns all characters containing `part`, ordered by their code point. Unlike `find` this
+    /// also finds unencoded components unknown to the built in `PuaMap`.
+    pub fn find_part(&self, part: CompositionPart, filter: ComponentFilter) -> Vec<char> {
+        match component_key(&part) {
+            Some(key) => utils::sorted(self.find_set(key, filter)),
+            None => vec![],
+        }
+    }
+
+    /// Returns all characters matching `query`, ordered by their code point
+    pub fn query(&self, query: &ComponentQuery) -> Vec<char> {
+        let filter = query.filter;
+
+        let mut result: HashSet<char> = match query.all_of.split_first() {
+            Some((first, rest)) => {
+                let mut set = self.find_set(char_key(*first), filter);
+                for component in rest {
+                    let other = self.find_set(char_key(*component), filter);
+                    set.retain(|i| other.contains(i));
+                }
+                set
+            }
+            None => self.literals.iter().copied().collect(),
+        };
+
+        if !query.any_of.is_empty() {
+            let any: HashSet<char> = query
+                .any_of
+                .iter()
+                .flat_map(|i| self.find_set(char_key(*i), filter))
+                .collect();
+            result.retain(|i| any.contains(i));
+        }
+
+        for component in &query.none_of {
+            let other = self.find_set(char_key(*component), filter);
+            result.retain(|i| !other.contains(i));
+        }
+
+        utils::sorted(result)
+    }
+
+    /// Returns `true` if there is no indexed component
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.postings.is_empty()
+    }
+
+    fn find_set(&self, key: CompositionPart, filter: ComponentFilter) -> HashSet<char> {
+        let postings = match self.postings.get(&key) {
+            Some(postings) => postings,
+            None => return HashSet::new(),
+        };
+
+        postings
+            .iter()
+            .filter(|i| filter.nested || i.top_level)
+            .filter(|i| filter.origin.is_none_or(|o| i.origins.contains(o)))
+            .map(|i| i.literal)
+            .collect()
+    }
+}
+
+impl IdsDatabase {
+    /// Builds a `ComponentIndex` over all entries
+    #[inline]
+    pub fn component_index(&self) -> ComponentIndex {
+        ComponentIndex::new(self)
+    }
+}
+
+/// Returns the key a component gets indexed and counted by. Radicals are folded by
+/// `map_special_form`, unencoded components are kept by their number so unknown ones stay
+/// distinct. Returns `None` for parts which aren't components.
+pub(crate) fn component_key(part: &CompositionPart) -> Option<CompositionPart> {
+    match part {
+        CompositionPart::Radical(r) => Some(CompositionPart::Radical(map_special_form(*r))),
+        CompositionPart::UnencodedComponent(_) => Some(*part),
+        _ => None,
+    }
+}
+
+/// Returns the key of a component given as character. PUA characters of the built in `PuaMap`
+/// are keyed as their unencoded component.
+pub(crate) fn char_key(c: char) -> CompositionPart {
+    match PuaMap::builtin().number_of(c) {
+        Some(nr) => CompositionPart::UnencodedComponent(nr),
+        None => CompositionPart::Radical(map_special_form(c)),
+    }
+}
+
+/// Collects all components of `node`. `depth` is the amount of enclosing `DestructionForm`s, a
+/// component is top level if it appears at a depth of at most one. Components are keyed by
+/// `component_key`.
+pub(crate) fn collect_components(
+    node: &IdsNode,
+    depth: usize,
+    out: &mut HashMap<CompositionPart, bool>,
+) {
+    match node {
+        IdsNode::Operator(_, children) => {
+            for child in children {
+                collect_components(child, depth + 1, out);
+            }
+        }
+        IdsNode::Modified(_, child) => collect_components(child, depth, out),
+        IdsNode::Leaf(part) => {
+            if let Some(key) = component_key(part) {
+                *out.entry(key).or_default() |= depth <= 1;
+            }
+        }
+    }
+}
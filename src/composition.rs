@@ -1,20 +1,41 @@
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use std::{fmt::Display, str::FromStr};
 
 use crate::{
-    destr_form::DestructionForm, error::ParseError, map_special_form, origin::Origin,
-    sepecial_components::conv_special,
+    destr_form::DestructionForm,
+    error::{ErrorKind, Field, ParseError},
+    origin::Origin,
+    origin_set::OriginSet,
+    pua_map::PuaMap,
+    radical_map::{Placement, RadicalMap},
+    tree::IdsNode,
 };
 
 /// A single composition of the format "^⿳亠口冋$(GHJKTV)"
-#[derive(Default, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Default, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Composition {
-    pub reg_origins: Vec<Origin>,
+    pub reg_origins: OriginSet,
+    /// Origins of `reg_origins` written in brackets, which are supplementary and not attested
+    pub bracketed_origins: OriginSet,
+    /// Free text following the origin list, or directly following the '$' if there is none
+    pub note: Option<String>,
+    /// The composition in its flat prefix ordered form, eg. [⿳, 亠, 口, 冋]
+    #[deprecated(note = "use `tree`, or `data()` which is always in sync with it")]
     pub data: Vec<CompositionPart>,
+    pub tree: IdsNode,
+}
+
+/// How an origin of a `Composition` is annotated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Annotation {
+    /// The composition is attested for the origin
+    Plain,
+    /// The composition is only supplementary or unverified for the origin
+    Bracketed,
 }
 
 /// A single part of the full composition
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum CompositionPart {
     Destructive(DestructionForm),
     Radical(char),
@@ -82,9 +103,23 @@ impl CompositionPart {
         matches!(self, Self::UnencodedComponent(..))
     }
 
+    /// Returns the displayable character of a radical or a decoded unencoded component.
+    /// Unencoded components unknown to the built in `PuaMap` are returned as
+    /// `PuaMap::REPLACEMENT`.
+    #[inline]
+    pub fn component(&self) -> Option<char> {
+        match self {
+            CompositionPart::Radical(r) => Some(*r),
+            CompositionPart::UnencodedComponent(_) => self.as_unencoded_component(),
+            _ => None,
+        }
+    }
+
+    /// Returns the PUA character of an unencoded component, or `PuaMap::REPLACEMENT` if the
+    /// built in `PuaMap` doesn't know it
     pub fn as_unencoded_component(&self) -> Option<char> {
         if let Self::UnencodedComponent(v) = self {
-            conv_special(*v)
+            Some(PuaMap::builtin().display(*v))
         } else {
             None
         }
@@ -92,7 +127,7 @@ impl CompositionPart {
 }
 
 /// A modifier for another component
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Modifier {
     UnrepresntableCompontent,
     IdeographicVariation,
@@ -101,7 +136,7 @@ pub enum Modifier {
     Subtraction,
 }
 
-/// Converts an character of a composition into a `CompositionPart`
+/// Converts an character of a composition into a `CompositionPart`. Radicals are kept as they are
 impl TryFrom<char> for CompositionPart {
     type Error = ParseError;
 
@@ -113,32 +148,76 @@ impl TryFrom<char> for CompositionPart {
         if let Ok(modifier) = Modifier::try_from(value) {
             return Ok(CompositionPart::Modifier(modifier));
         }
-        let mapped = map_special_form(value);
-        Ok(CompositionPart::Radical(mapped))
+        Ok(CompositionPart::Radical(value))
     }
 }
 
 /// Converts a composition string to a `Composition`. Format of the composition string: ^⿳亠口冋$(GHJKTV)
+///
+/// Radicals are kept as they are written, so formatting the composition reproduces the input.
+/// Earlier versions folded variants while parsing, eg. 牜 to 牛 and 艹 to 艸. Use
+/// `Composition::parse_with(s, RadicalMap::standard())` to keep doing so, or
+/// `IdsNode::normalized` to fold them afterwards.
 impl FromStr for Composition {
     type Err = ParseError;
 
+    #[inline]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut s_iter = s.chars();
-        let first = s_iter.next().ok_or(ParseError::InvalidComposition)?;
+        Composition::parse_with(s, &RadicalMap::new())
+    }
+}
+
+impl CompositionPart {
+    /// Converts a character of a composition into a `CompositionPart`. Radicals get folded
+    /// using those variants of `map` which are valid at any placement.
+    #[inline]
+    pub fn parse(value: char, map: &RadicalMap) -> Result<Self, ParseError> {
+        Ok(CompositionPart::try_from(value)?.folded(map, Placement::Any))
+    }
+
+    /// Folds a radical into its base form if it is a variant of `map` placed at `placement`.
+    /// Other parts are returned as they are.
+    #[inline]
+    pub fn folded(&self, map: &RadicalMap, placement: Placement) -> Self {
+        match self {
+            CompositionPart::Radical(r) => CompositionPart::Radical(map.fold(*r, placement)),
+            part => *part,
+        }
+    }
+}
+
+impl Composition {
+    /// Parses a composition string, folding radical variants with `map` while parsing. Parsing
+    /// with `FromStr` keeps all radicals as they are.
+    pub fn parse_with(s: &str, map: &RadicalMap) -> Result<Self, ParseError> {
+        let err = |kind: ErrorKind, column: usize| {
+            ParseError::new(kind)
+                .at(column)
+                .in_field(Field::Composition, s)
+        };
+
+        let mut s_iter = s.chars().enumerate();
+        let (_, first) = s_iter
+            .next()
+            .ok_or_else(|| err(ErrorKind::InvalidComposition, 0))?;
         if first != '^' {
-            return Err(ParseError::InvalidComposition);
+            return Err(err(ErrorKind::InvalidComposition, 0));
         }
 
         let mut parts = vec![];
+        // Column of each part within `s`
+        let mut columns = vec![];
+        let mut end = s.chars().count();
 
-        while let Some(part) = s_iter.next() {
+        while let Some((column, part)) = s_iter.next() {
             if part == '$' {
+                end = column;
                 break;
             }
 
             if part == '{' {
                 let mut unenc_nr = String::new();
-                while let Some(pnr) = s_iter.next() {
+                for (_, pnr) in s_iter.by_ref() {
                     if pnr == '}' {
                         break;
                     }
@@ -146,36 +225,82 @@ impl FromStr for Composition {
                 }
                 let unenc_nr: u32 = unenc_nr
                     .parse()
-                    .map_err(|_| ParseError::UnexpectedCharacter)?;
+                    .map_err(|_| err(ErrorKind::UnexpectedCharacter, column))?;
                 parts.push(resolve_unencoded_comp(unenc_nr));
+                columns.push(column);
                 continue;
             }
 
-            parts.push(CompositionPart::try_from(part)?);
+            let part = CompositionPart::parse(part, map).map_err(|e| err(e.kind, column))?;
+            parts.push(part);
+            columns.push(column);
         }
 
-        let mut origins = vec![];
+        let mut origins = OriginSet::new();
+        let mut bracketed_origins = OriginSet::new();
+        let mut note = None;
 
-        // should now be left in s_iter: (GHJKTV)
-        if let Some(next) = s_iter.next() {
-            if next == '(' {
-                while let Some(n) = s_iter.next() {
-                    if n == ')' {
-                        break;
-                    }
-                    if n == '[' || n == ']' {
-                        continue;
+        // should now be left: (G[T]) and an optional note
+        let tail: String = s_iter.map(|i| i.1).collect();
+        if let Some(list) = tail.strip_prefix('(') {
+            // Column of the first origin
+            let offset = end + 2;
+            let unexpected = |column: usize| err(ErrorKind::UnexpectedCharacter, column);
+
+            let Some((list, rest)) = list.split_once(')') else {
+                return Err(unexpected(offset + list.chars().count()));
+            };
+            if list.is_empty() {
+                return Err(unexpected(offset));
+            }
+
+            // Column of the currently open '['
+            let mut open_bracket = None;
+            for (i, n) in list.chars().enumerate() {
+                let column = offset + i;
+                let in_brackets = open_bracket.is_some();
+                match n {
+                    '[' if in_brackets => return Err(unexpected(column)),
+                    '[' => open_bracket = Some(column),
+                    // Brackets have to be closed and contain at least one origin
+                    ']' if open_bracket.is_none_or(|i| i + 1 == column) => {
+                        return Err(unexpected(column))
                     }
-                    if let Ok(origin) = Origin::try_from(n) {
-                        origins.push(origin);
+                    ']' => open_bracket = None,
+                    _ => {
+                        let origin = Origin::try_from(n).map_err(|e| err(e.kind, column))?;
+                        origins.insert(origin);
+                        if in_brackets {
+                            bracketed_origins.insert(origin);
+                        }
                     }
                 }
             }
+            if let Some(column) = open_bracket {
+                return Err(unexpected(column));
+            }
+
+            if !rest.is_empty() {
+                note = Some(rest.to_string());
+            }
+        } else if !tail.is_empty() {
+            // A note without an origin list
+            note = Some(tail);
         }
 
+        // Errors of the tree point to the part they occurred at
+        let tree = IdsNode::from_parts(&parts).map_err(|e| {
+            let column = e.column.and_then(|i| columns.get(i).copied());
+            err(e.kind, column.unwrap_or(end))
+        })?;
+
+        #[allow(deprecated)]
         Ok(Composition {
-            data: parts,
+            data: tree.to_parts(),
+            tree,
             reg_origins: origins,
+            bracketed_origins,
+            note,
         })
     }
 }
@@ -196,12 +321,84 @@ impl TryFrom<char> for Modifier {
     }
 }
 
+impl From<Modifier> for char {
+    #[inline]
+    fn from(modifier: Modifier) -> Self {
+        match modifier {
+            Modifier::UnrepresntableCompontent => '？',
+            Modifier::IdeographicVariation => '〾',
+            Modifier::Mirror => '↔',
+            Modifier::Rotation => '↷',
+            Modifier::Subtraction => '⊖',
+        }
+    }
+}
+
+impl Display for Modifier {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", char::from(*self))
+    }
+}
+
+/// Formats the part the way it is written in a composition string
+impl Display for CompositionPart {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompositionPart::Destructive(form) => write!(f, "{form}"),
+            CompositionPart::Radical(r) => write!(f, "{r}"),
+            CompositionPart::Modifier(modifier) => write!(f, "{modifier}"),
+            CompositionPart::UnencodedComponent(nr) => write!(f, "{{{nr:02}}}"),
+        }
+    }
+}
+
+/// Formats the composition as composition string: ^⿳亠口冋$(GHJKTV). Radicals are written as they
+/// were parsed and origins in canonical order. Fails for origins without an IDS.TXT letter, see
+/// `OriginSet::has_letters`.
+impl Display for Composition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "^{}$", self.tree)?;
+        if !self.reg_origins.is_empty() {
+            write!(f, "(")?;
+            let mut in_brackets = false;
+            for origin in self.reg_origins.iter() {
+                let bracketed = self.bracketed_origins.contains(origin);
+                if bracketed != in_brackets {
+                    write!(f, "{}", if bracketed { '[' } else { ']' })?;
+                    in_brackets = bracketed;
+                }
+                write!(f, "{}", origin.letter().ok_or(std::fmt::Error)?)?;
+            }
+            if in_brackets {
+                write!(f, "]")?;
+            }
+            write!(f, ")")?;
+        }
+        if let Some(note) = &self.note {
+            write!(f, "{note}")?;
+        }
+        Ok(())
+    }
+}
+
 impl Modifier {
     /// Returns `true` if `c` is a `Modifier`
     #[inline]
     pub fn is_modifier(c: char) -> bool {
         Modifier::try_from(c).is_ok()
     }
+
+    /// Returns the amount of components the modifier gets applied to. An unrepresentable
+    /// component stands for a component itself and thus takes none.
+    #[inline]
+    pub fn arity(&self) -> usize {
+        match self {
+            Modifier::UnrepresntableCompontent => 0,
+            _ => 1,
+        }
+    }
 }
 
 #[inline]
@@ -210,12 +407,89 @@ fn resolve_unencoded_comp(nr: u32) -> CompositionPart {
 }
 
 impl Composition {
+    /// Brings the tree of the composition into its canonical form. See `IdsNode::normalized`
+    #[inline]
+    #[allow(deprecated)]
+    pub fn normalize(&mut self) {
+        self.tree = self.tree.normalized();
+        self.data = self.tree.to_parts();
+    }
+
+    /// Returns the normalized tree, which is the same for all compositions describing the same
+    /// layout regardless of their origins and note. Use it to compare, hash or deduplicate
+    /// compositions, as `Eq` and `Hash` of `Composition` take all fields into account.
+    #[inline]
+    pub fn key(&self) -> IdsNode {
+        self.tree.normalized()
+    }
+
+    /// Returns `true` if both compositions describe the same layout. See `Composition::key`
+    #[inline]
+    pub fn same_layout(&self, other: &Composition) -> bool {
+        self.key() == other.key()
+    }
+
+    /// Adds the origins of `other` to the composition. Origins stay bracketed only if they are
+    /// bracketed in both compositions.
+    pub fn merge_origins(&mut self, other: &Composition) {
+        let (own, others) = (self.bracketed_origins, other.bracketed_origins);
+        self.bracketed_origins =
+            (own - other.reg_origins) | (own & others) | (others - self.reg_origins);
+        self.reg_origins = self.reg_origins | other.reg_origins;
+    }
+
+    /// Returns how `origin` is annotated or `None` if the composition isn't used for `origin`
+    #[inline]
+    pub fn annotation(&self, origin: Origin) -> Option<Annotation> {
+        if !self.reg_origins.contains(origin) {
+            return None;
+        }
+        if self.bracketed_origins.contains(origin) {
+            Some(Annotation::Bracketed)
+        } else {
+            Some(Annotation::Plain)
+        }
+    }
+
+    /// Returns `true` if the composition is attested for `origin`, ie. listed without brackets
+    #[inline]
+    pub fn is_attested(&self, origin: Origin) -> bool {
+        self.annotation(origin) == Some(Annotation::Plain)
+    }
+
+    /// Returns the composition in its flat prefix ordered form, eg. [⿳, 亠, 口, 冋]
+    #[inline]
+    pub fn data(&self) -> Vec<CompositionPart> {
+        self.tree.to_parts()
+    }
+
+    /// Returns `true` if the composition of `literal` consists of nothing but `literal` itself
+    #[inline]
+    pub fn is_atomic(&self, literal: char) -> bool {
+        match self.tree {
+            IdsNode::Leaf(CompositionPart::Radical(r)) => {
+                r == literal || r == RadicalMap::standard().fold(literal, Placement::Any)
+            }
+            _ => false,
+        }
+    }
+
     /// Returns an iterator over all displayable radicals, including decoded unencoded components.
+    /// Unencoded components unknown to the built in `PuaMap` are returned as
+    /// `PuaMap::REPLACEMENT`. Radicals are returned as parsed, see `Composition::from_str`.
     pub fn get_radicals(&self) -> impl Iterator<Item = char> + '_ {
-        self.data.iter().filter_map(|i| match i {
-            CompositionPart::Radical(r) => Some(*r),
-            CompositionPart::UnencodedComponent(c) => conv_special(*c),
-            _ => None,
-        })
+        self.tree.leaves().filter_map(|i| i.component())
+    }
+
+    /// Returns all radicals, decoding unencoded components with `map`. Fails on the first
+    /// unencoded component `map` has neither a character nor a fallback for.
+    pub fn get_radicals_with(&self, map: &PuaMap) -> Result<Vec<char>, ParseError> {
+        self.tree
+            .leaves()
+            .filter_map(|i| match i {
+                CompositionPart::UnencodedComponent(nr) => Some(map.get(*nr)),
+                part => part.component().map(Ok),
+            })
+            .collect()
     }
 }
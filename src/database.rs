@@ -0,0 +1,201 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+    str::FromStr,
+    sync::OnceLock,
+};
+
+use crate::{
+    error::{LoadError, ParseError},
+    kangxi,
+    tree::IdsNode,
+    IDS,
+};
+
+/// How to handle lines which can't be parsed while loading an IDS file
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum LoadMode {
+    /// Abort loading on the first invalid line
+    #[default]
+    Strict,
+    /// Skip invalid lines and collect their errors
+    Lenient,
+}
+
+/// An in-memory dataset of all `IDS` entries of an IDS.TXT file
+#[derive(Default, Clone, Debug)]
+pub struct IdsDatabase {
+    entries: Vec<IDS>,
+    by_literal: HashMap<char, usize>,
+    /// Entries whose literal consists of more than one code point
+    by_sequence: HashMap<String, usize>,
+    errors: Vec<ParseError>,
+    warnings: Vec<ParseError>,
+    /// Normalized trees of the compositions of each entry, computed on first use
+    normalized: OnceLock<Vec<Vec<IdsNode>>>,
+}
+
+impl IdsDatabase {
+    /// Creates a new and empty `IdsDatabase`
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the IDS file at `path`
+    pub fn from_path<P: AsRef<Path>>(path: P, mode: LoadMode) -> Result<Self, LoadError> {
+        Self::from_reader(File::open(path)?, mode)
+    }
+
+    /// Loads all IDS entries from `reader`. Comments, empty lines and the BOM get skipped.
+    pub fn from_reader<R: Read>(reader: R, mode: LoadMode) -> Result<Self, LoadError> {
+        let mut db = Self::new();
+
+        for (nr, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            let line = line.trim_start_matches('\u{feff}').trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let parsed = match mode {
+                LoadMode::Strict => IDS::from_str(line).map(|ids| (ids, vec![])),
+                LoadMode::Lenient => IDS::from_str_lenient(line),
+            };
+
+            match parsed {
+                Ok((ids, warnings)) => {
+                    db.insert(ids);
+                    let warnings = warnings.into_iter().map(|i| i.on_line(nr + 1));
+                    db.warnings.extend(warnings);
+                }
+                Err(err) => {
+                    let err = err.on_line(nr + 1);
+                    if mode == LoadMode::Strict {
+                        return Err(err.into());
+                    }
+                    db.errors.push(err);
+                }
+            }
+        }
+
+        Ok(db)
+    }
+
+    /// Inserts an `IDS` entry. An existing entry with the same literal gets replaced.
+    pub fn insert(&mut self, ids: IDS) {
+        self.normalized = OnceLock::new();
+
+        let pos = match &ids.sequence {
+            Some(sequence) => self.by_sequence.get(sequence),
+            None => self.by_literal.get(&ids.literal),
+        };
+        if let Some(pos) = pos {
+            self.entries[*pos] = ids;
+            return;
+        }
+
+        match &ids.sequence {
+            Some(sequence) => self
+                .by_sequence
+                .insert(sequence.clone(), self.entries.len()),
+            None => self.by_literal.insert(ids.literal, self.entries.len()),
+        };
+        self.entries.push(ids);
+    }
+
+    /// Returns the `IDS` entry of `literal`
+    #[inline]
+    pub fn get(&self, literal: char) -> Option<&IDS> {
+        self.by_literal.get(&literal).map(|i| &self.entries[*i])
+    }
+
+    /// Returns the `IDS` entry of `literal`. Kangxi radicals and CJK Radicals Supplement forms
+    /// get looked up by their unified ideograph.
+    #[inline]
+    pub fn get_normalized(&self, literal: char) -> Option<&IDS> {
+        self.get(kangxi::to_unified(literal))
+    }
+
+    /// Returns the `IDS` entry of a literal consisting of more than one code point, eg. one with
+    /// a variation selector. Falls back to the entry of a single character.
+    #[inline]
+    pub fn get_sequence(&self, literal: &str) -> Option<&IDS> {
+        if let Some(pos) = self.by_sequence.get(literal) {
+            return Some(&self.entries[*pos]);
+        }
+
+        let mut chars = literal.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => self.get(c),
+            _ => None,
+        }
+    }
+
+    /// Returns the `IDS` entry of the character with the given unicode code point
+    #[inline]
+    pub fn get_by_code_point(&self, code_point: u32) -> Option<&IDS> {
+        self.get(char::from_u32(code_point)?)
+    }
+
+    /// Returns the normalized trees of the compositions of all entries, in the order of `iter`.
+    /// They get computed on the first call and are kept until the next `insert`.
+    pub(crate) fn normalized_trees(&self) -> &[Vec<IdsNode>] {
+        self.normalized.get_or_init(|| {
+            self.entries
+                .iter()
+                .map(|ids| ids.compositions.iter().map(|i| i.key()).collect())
+                .collect()
+        })
+    }
+
+    /// Returns the normalized trees of the compositions of `literal`. See `normalized_trees`
+    #[inline]
+    pub(crate) fn normalized_trees_of(&self, literal: char) -> Option<&[IdsNode]> {
+        let pos = self.by_literal.get(&literal)?;
+        Some(&self.normalized_trees()[*pos])
+    }
+
+    /// Returns an iterator over all entries in the order they were loaded
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &IDS> {
+        self.entries.iter()
+    }
+
+    /// Returns the errors of all lines skipped while loading in `LoadMode::Lenient`
+    #[inline]
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    /// Returns the errors of all invalid parts of loaded lines, like cross references, which got
+    /// skipped in `LoadMode::Lenient`
+    #[inline]
+    pub fn warnings(&self) -> &[ParseError] {
+        &self.warnings
+    }
+
+    /// Returns the amount of entries
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no entries
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl FromIterator<IDS> for IdsDatabase {
+    fn from_iter<T: IntoIterator<Item = IDS>>(iter: T) -> Self {
+        let mut db = Self::new();
+        for ids in iter {
+            db.insert(ids);
+        }
+        db
+    }
+}
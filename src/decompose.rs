@@ -0,0 +1,73 @@
+use crate::{
+    composition::CompositionPart, database::IdsDatabase, preference::RegionPreference,
+    tree::IdsNode, Composition,
+};
+
+/// Options for the recursive decomposition of a character
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DecomposeOptions {
+    /// Selects the regional composition to follow at every level. With the default preference
+    /// the first composition of each entry is used.
+    pub preference: RegionPreference,
+    /// Maximum amount of levels to expand, with the composition of the character itself being
+    /// the first level. `None` expands until only atomic components are left.
+    pub max_depth: Option<usize>,
+}
+
+impl IdsDatabase {
+    /// Recursively decomposes `literal` by expanding each radical through its own entry until
+    /// only atomic components are left. Returns `None` if there is no entry for `literal`.
+    pub fn decompose(&self, literal: char, options: DecomposeOptions) -> Option<IdsNode> {
+        let composition = self.select_composition(literal, &options.preference)?;
+        let mut stack = vec![literal];
+        Some(self.expand(&composition.tree, &options, &mut stack))
+    }
+
+    /// Returns the atomic components of `literal` from left to right
+    pub fn decompose_components(&self, literal: char, options: DecomposeOptions) -> Vec<char> {
+        self.decompose(literal, options)
+            .map(|tree| tree.leaves().filter_map(|i| i.component()).collect())
+            .unwrap_or_default()
+    }
+
+    fn select_composition(
+        &self,
+        literal: char,
+        preference: &RegionPreference,
+    ) -> Option<&Composition> {
+        Some(self.get(literal)?.select(preference)?.composition)
+    }
+
+    /// Expands all radicals of `node`. `stack` contains the characters currently being expanded
+    fn expand(&self, node: &IdsNode, options: &DecomposeOptions, stack: &mut Vec<char>) -> IdsNode {
+        match node {
+            IdsNode::Operator(form, children) => {
+                let children = children
+                    .iter()
+                    .map(|i| self.expand(i, options, stack))
+                    .collect();
+                IdsNode::Operator(*form, children)
+            }
+            IdsNode::Modified(modifier, child) => {
+                IdsNode::Modified(*modifier, Box::new(self.expand(child, options, stack)))
+            }
+            IdsNode::Leaf(CompositionPart::Radical(radical)) => {
+                // Don't follow cycles
+                if stack.contains(radical) || options.max_depth.is_some_and(|d| stack.len() >= d) {
+                    return node.clone();
+                }
+
+                let composition = match self.select_composition(*radical, &options.preference) {
+                    Some(composition) if !composition.is_atomic(*radical) => composition,
+                    _ => return node.clone(),
+                };
+
+                stack.push(*radical);
+                let expanded = self.expand(&composition.tree, options, stack);
+                stack.pop();
+                expanded
+            }
+            IdsNode::Leaf(_) => node.clone(),
+        }
+    }
+}
@@ -1,9 +1,11 @@
+use std::fmt::Display;
+
 use serde::{Deserialize, Serialize};
 
-use crate::error::ParseError;
+use crate::error::{ErrorKind, ParseError};
 
 /// Describing in which form radicals get combined with each other
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum DestructionForm {
     Vertically,
     Horizontally,
@@ -17,7 +19,14 @@ pub enum DestructionForm {
     BoxOpenBottomRight,
     BoxOpenBottomLeft,
     BoxOpenTopRight,
+    BoxOpenTopLeft,
     Diagonal,
+    /// Horizontal reflection of a single component
+    Reflection,
+    /// 180° rotation of a single component
+    Rotation,
+    /// The first component with the second one removed
+    Subtraction,
 }
 
 impl TryFrom<char> for DestructionForm {
@@ -38,7 +47,56 @@ impl TryFrom<char> for DestructionForm {
             '⿹' => DestructionForm::BoxOpenBottomLeft,
             '⿺' => DestructionForm::BoxOpenTopRight,
             '⿻' => DestructionForm::Diagonal,
-            _ => return Err(ParseError::InvalidDestructiveForm(c)),
+            '⿼' => DestructionForm::BoxOpenLeft,
+            '⿽' => DestructionForm::BoxOpenTopLeft,
+            '⿾' => DestructionForm::Reflection,
+            '⿿' => DestructionForm::Rotation,
+            '㇯' => DestructionForm::Subtraction,
+            _ => return Err(ErrorKind::InvalidDestructiveForm(c).into()),
         })
     }
 }
+
+impl DestructionForm {
+    /// Returns the amount of components combined by the form
+    #[inline]
+    pub fn arity(&self) -> usize {
+        match self {
+            DestructionForm::Reflection | DestructionForm::Rotation => 1,
+            DestructionForm::Vertically3 | DestructionForm::Horizontally3 => 3,
+            _ => 2,
+        }
+    }
+}
+
+impl From<DestructionForm> for char {
+    #[inline]
+    fn from(form: DestructionForm) -> Self {
+        match form {
+            DestructionForm::Vertically => '⿰',
+            DestructionForm::Horizontally => '⿱',
+            DestructionForm::Vertically3 => '⿲',
+            DestructionForm::Horizontally3 => '⿳',
+            DestructionForm::BoxInner => '⿴',
+            DestructionForm::BoxOpenBottom => '⿵',
+            DestructionForm::BoxOpenTop => '⿶',
+            DestructionForm::BoxOpenRight => '⿷',
+            DestructionForm::BoxOpenLeft => '⿼',
+            DestructionForm::BoxOpenBottomRight => '⿸',
+            DestructionForm::BoxOpenBottomLeft => '⿹',
+            DestructionForm::BoxOpenTopRight => '⿺',
+            DestructionForm::BoxOpenTopLeft => '⿽',
+            DestructionForm::Diagonal => '⿻',
+            DestructionForm::Reflection => '⿾',
+            DestructionForm::Rotation => '⿿',
+            DestructionForm::Subtraction => '㇯',
+        }
+    }
+}
+
+impl Display for DestructionForm {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", char::from(*self))
+    }
+}
@@ -1,8 +1,25 @@
 use std::fmt::Display;
 
+use crate::{composition::Modifier, destr_form::DestructionForm};
+
 /// Error occurring during parsing process
-#[derive(Debug, Copy, Clone)]
-pub enum ParseError {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ErrorKind,
+    /// The field of the IDS line the error occurred in
+    pub field: Option<Field>,
+    /// 1-based line number, if the error occurred while parsing a file
+    pub line: Option<usize>,
+    /// 0-based char position of the error within `input`. Messages show it 1-based, like editors
+    /// do
+    pub column: Option<usize>,
+    /// The text of the field that failed to parse
+    pub input: Option<String>,
+}
+
+/// The kind of error occurring during parsing process
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
     InvalidOrigin(char),
     InvalidDestructiveForm(char),
     InvalidRefType(char),
@@ -10,12 +27,202 @@ pub enum ParseError {
     InvalidXRef,
     InvalidComposition,
     UnexpectedCharacter,
+    /// The composition doesn't contain any component
+    EmptyComposition,
+    /// A `DestructionForm` got less operands than it requires
+    MissingOperand(DestructionForm),
+    /// A `Modifier` is not followed by the component it modifies
+    DanglingModifier(Modifier),
+    /// Components left after the composition is complete. Holds the amount of left components
+    TrailingComponents(usize),
+    /// The code point field is not of the format `U+XXXX`
+    InvalidCodePoint,
+    /// The literal field is empty
+    MissingLiteral,
+    /// A field is neither a composition nor a cross reference. The column is the start of the
+    /// field within the line
+    UnknownField,
+    /// The literal doesn't match the declared code point. Holds the declared code point
+    CodePointMismatch(u32),
+    /// There is neither a PUA character nor a fallback for the unencoded component
+    UnknownUnencodedComponent(u32),
+    /// The binary data doesn't start with the expected magic bytes
+    InvalidMagic,
+    /// The binary data is of an unsupported format version
+    UnsupportedVersion(u16),
+    /// The binary data ends in the middle of an item. The column is the byte offset
+    UnexpectedEnd,
+    /// The binary data contains an invalid item. The column is the byte offset
+    InvalidBinary,
+}
+
+/// The tab separated fields of an IDS line
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Field {
+    CodePoint,
+    Literal,
+    Composition,
+    XRef,
+}
+
+impl ParseError {
+    #[inline]
+    pub fn new(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            field: None,
+            line: None,
+            column: None,
+            input: None,
+        }
+    }
+
+    /// Sets the column of the error within its field
+    #[inline]
+    pub fn at(mut self, column: usize) -> Self {
+        self.column = Some(column);
+        self
+    }
+
+    /// Moves the column by `offset` chars. Used if the failing input was only a part of the field
+    #[inline]
+    pub fn shifted(mut self, offset: usize) -> Self {
+        self.column = Some(self.column.unwrap_or(0) + offset);
+        self
+    }
+
+    /// Sets the field and its text, unless they are already known
+    #[inline]
+    pub fn in_field(mut self, field: Field, input: &str) -> Self {
+        if self.field.is_none() {
+            self.field = Some(field);
+            self.input = Some(input.to_string());
+        }
+        self
+    }
+
+    /// Sets the line number the error occurred in
+    #[inline]
+    pub fn on_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+}
+
+impl From<ErrorKind> for ParseError {
+    #[inline]
+    fn from(kind: ErrorKind) -> Self {
+        ParseError::new(kind)
+    }
 }
 
 impl Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:#?}", self)
+        if let Some(line) = self.line {
+            write!(f, "line {line}: ")?;
+        }
+        if let Some(field) = self.field {
+            write!(f, "{field} field: ")?;
+        }
+        write!(f, "{}", self.kind)?;
+        match (self.kind, self.column) {
+            (ErrorKind::UnexpectedEnd | ErrorKind::InvalidBinary, Some(offset)) => {
+                write!(f, " at byte {offset}")?
+            }
+            (_, Some(column)) => write!(f, " at column {}", column + 1)?,
+            (_, None) => (),
+        }
+
+        if let Some(input) = &self.input {
+            write!(f, "\n    {input}")?;
+            if let Some(column) = self.column {
+                // CJK characters take up two columns in a terminal
+                let width: usize = input
+                    .chars()
+                    .take(column)
+                    .map(|c| if c.is_ascii() { 1 } else { 2 })
+                    .sum();
+                write!(f, "\n    {}^", " ".repeat(width))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::InvalidOrigin(c) => write!(f, "invalid origin {c:?}"),
+            ErrorKind::InvalidDestructiveForm(c) => write!(f, "invalid destruction form {c:?}"),
+            ErrorKind::InvalidRefType(c) => write!(f, "invalid reference type {c:?}"),
+            ErrorKind::InvalidIDS => write!(f, "invalid IDS line"),
+            ErrorKind::InvalidXRef => write!(f, "invalid cross reference"),
+            ErrorKind::InvalidComposition => write!(f, "invalid composition"),
+            ErrorKind::UnexpectedCharacter => write!(f, "unexpected character"),
+            ErrorKind::EmptyComposition => write!(f, "empty composition"),
+            ErrorKind::MissingOperand(form) => write!(f, "missing operand for {form}"),
+            ErrorKind::DanglingModifier(m) => write!(f, "modifier {m} without component"),
+            ErrorKind::TrailingComponents(n) => write!(f, "{n} trailing component(s)"),
+            ErrorKind::InvalidCodePoint => write!(f, "invalid code point"),
+            ErrorKind::MissingLiteral => write!(f, "missing literal"),
+            ErrorKind::UnknownField => write!(f, "unknown field"),
+            ErrorKind::CodePointMismatch(cp) => {
+                write!(f, "literal doesn't match code point U+{cp:04X}")
+            }
+            ErrorKind::UnknownUnencodedComponent(nr) => {
+                write!(f, "unknown unencoded component {{{nr:02}}}")
+            }
+            ErrorKind::InvalidMagic => write!(f, "not an IDS blob"),
+            ErrorKind::UnsupportedVersion(v) => write!(f, "unsupported format version {v}"),
+            ErrorKind::UnexpectedEnd => write!(f, "unexpected end of data"),
+            ErrorKind::InvalidBinary => write!(f, "invalid binary data"),
+        }
+    }
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Field::CodePoint => "code point",
+            Field::Literal => "literal",
+            Field::Composition => "composition",
+            Field::XRef => "xref",
+        };
+        write!(f, "{name}")
     }
 }
 
 impl std::error::Error for ParseError {}
+
+/// Error occurring while loading a whole IDS file
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    Parse(ParseError),
+}
+
+impl From<std::io::Error> for LoadError {
+    #[inline]
+    fn from(err: std::io::Error) -> Self {
+        LoadError::Io(err)
+    }
+}
+
+impl From<ParseError> for LoadError {
+    #[inline]
+    fn from(err: ParseError) -> Self {
+        LoadError::Parse(err)
+    }
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "{err}"),
+            LoadError::Parse(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
@@ -1,13 +1,23 @@
-use std::str::FromStr;
+use std::{collections::HashMap, fmt::Display, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{composition::Composition, error::ParseError, Origin, XRef};
+use crate::{
+    composition::Composition,
+    error::{ErrorKind, Field, ParseError},
+    tree::IdsNode,
+    utils, Origin, XRef,
+};
 
 /// A full Ideographic Destruction Sequence item
 #[derive(Default, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct IDS {
+    /// The code point declared in the first field of the line
+    pub code_point: u32,
     pub literal: char,
+    /// The full literal, if it consists of more than one code point, eg. if it contains a
+    /// variation selector
+    pub sequence: Option<String>,
     pub compositions: Vec<Composition>,
     pub xrefs: Vec<XRef>,
 }
@@ -16,45 +26,160 @@ pub struct IDS {
 impl FromStr for IDS {
     type Err = ParseError;
 
+    #[inline]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        IDS::parse(s, None)
+    }
+}
+
+impl IDS {
+    /// Parses an `IDS` like `from_str`, but collects errors of invalid cross references, unknown
+    /// fields and literals followed by anything but a variation selector as warnings instead of
+    /// failing. Those parts get skipped.
+    pub fn from_str_lenient(s: &str) -> Result<(Self, Vec<ParseError>), ParseError> {
+        let mut warnings = vec![];
+        let ids = IDS::parse(s, Some(&mut warnings))?;
+        Ok((ids, warnings))
+    }
+
+    /// Parses an `IDS`. Errors of skippable parts get pushed to `warnings` if given
+    fn parse(s: &str, mut warnings: Option<&mut Vec<ParseError>>) -> Result<Self, ParseError> {
+        let mut report = |err: ParseError| match warnings.as_mut() {
+            Some(warnings) => {
+                warnings.push(err);
+                Ok(())
+            }
+            None => Err(err),
+        };
         let mut split = s.split('\t');
-        let _utf8_code = split.next().ok_or(ParseError::InvalidIDS)?;
 
-        let literal = split
-            .next()
-            .ok_or(ParseError::InvalidIDS)?
-            .chars()
-            .next()
-            .unwrap();
+        // `split` always yields at least one item
+        let code_str = split.next().unwrap_or_default();
+        let code_point = utils::utf_code_to_char(code_str).ok_or_else(|| {
+            ParseError::new(ErrorKind::InvalidCodePoint)
+                .at(0)
+                .in_field(Field::CodePoint, code_str)
+        })? as u32;
+
+        let literal_str = split.next().unwrap_or_default();
+        let literal = literal_str.chars().next().ok_or_else(|| {
+            ParseError::new(ErrorKind::MissingLiteral).in_field(Field::Literal, literal_str)
+        })?;
+        if literal as u32 != code_point {
+            return Err(ParseError::new(ErrorKind::CodePointMismatch(code_point))
+                .at(0)
+                .in_field(Field::Literal, literal_str));
+        }
+
+        // Only a single variation selector may follow the literal
+        let mut sequence = None;
+        match literal_str.chars().skip(1).collect::<Vec<_>>().as_slice() {
+            [] => (),
+            [selector] if utils::is_variation_selector(*selector) => {
+                sequence = Some(literal_str.to_string())
+            }
+            [selector, ..] => {
+                let column = if utils::is_variation_selector(*selector) {
+                    2
+                } else {
+                    1
+                };
+                report(
+                    ParseError::new(ErrorKind::UnexpectedCharacter)
+                        .at(column)
+                        .in_field(Field::Literal, literal_str),
+                )?;
+            }
+        }
 
         let mut compositions = vec![];
         let mut xrefs = vec![];
+        // Column of the current field within the line
+        let mut column = code_str.chars().count() + literal_str.chars().count() + 2;
 
-        while let Some(part) = split.next() {
+        for part in split {
             if part.starts_with('^') {
                 let composition = Composition::from_str(part)?;
                 compositions.push(composition);
-            } else if part.starts_with('*') {
-                if let Some(xref) = XRef::from_str(&part[1..]).ok() {
-                    xrefs.push(xref);
+            } else if let Some(xref) = part.strip_prefix('*') {
+                match XRef::from_str(xref) {
+                    Ok(xref) => xrefs.push(xref),
+                    Err(err) => report(err)?,
                 }
+            } else {
+                report(ParseError::new(ErrorKind::UnknownField).at(column))?;
             }
+            column += part.chars().count() + 1;
         }
 
         Ok(IDS {
+            code_point,
             literal,
+            sequence,
             compositions,
             xrefs,
         })
     }
 }
 
+/// Formats the `IDS` as a line of IDS.TXT
+impl Display for IDS {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "U+{:04X}\t", self.code_point)?;
+        match &self.sequence {
+            Some(sequence) => write!(f, "{sequence}")?,
+            None => write!(f, "{}", self.literal)?,
+        }
+        for composition in &self.compositions {
+            write!(f, "\t{composition}")?;
+        }
+        for xref in &self.xrefs {
+            write!(f, "\t*{xref}")?;
+        }
+        Ok(())
+    }
+}
+
 impl IDS {
+    /// Returns the full literal of the entry, including variation selectors
+    #[inline]
+    pub fn literal_str(&self) -> String {
+        self.sequence
+            .clone()
+            .unwrap_or_else(|| self.literal.to_string())
+    }
+
     /// Returns the `Composition` with `origin`
     #[inline]
     pub fn comp_by_lang(&self, origin: Origin) -> Option<&Composition> {
         self.compositions
             .iter()
-            .find(|i| i.reg_origins.contains(&origin))
+            .find(|i| i.reg_origins.contains(origin))
+    }
+
+    /// Normalizes all compositions and merges those with the same `Composition::key`, no matter
+    /// their origins and notes
+    pub fn dedup_compositions(&mut self) {
+        let mut compositions: Vec<Composition> = vec![];
+        let mut keys: HashMap<IdsNode, usize> = HashMap::new();
+
+        for mut composition in self.compositions.drain(..) {
+            composition.normalize();
+            match keys.get(&composition.tree) {
+                Some(pos) => compositions[*pos].merge_origins(&composition),
+                None => {
+                    keys.insert(composition.tree.clone(), compositions.len());
+                    compositions.push(composition);
+                }
+            }
+        }
+
+        self.compositions = compositions;
+    }
+
+    /// Returns `true` if the only composition of the character is the character itself
+    #[inline]
+    pub fn is_atomic(&self) -> bool {
+        !self.compositions.is_empty() && self.compositions.iter().all(|i| i.is_atomic(self.literal))
     }
 }
@@ -0,0 +1,171 @@
+/// Unified ideographs of the Kangxi radicals (U+2F00 - U+2FD5), in the order of the block
+const KANGXI: [char; 214] = [
+    '一', '丨', '丶', '丿', '乙', '亅', '二', '亠', '人', '儿', '入', '八', '冂', '冖', '冫', '几',
+    '凵', '刀', '力', '勹', '匕', '匚', '匸', '十', '卜', '卩', '厂', '厶', '又', '口', '囗', '土',
+    '士', '夂', '夊', '夕', '大', '女', '子', '宀', '寸', '小', '尢', '尸', '屮', '山', '巛', '工',
+    '己', '巾', '干', '幺', '广', '廴', '廾', '弋', '弓', '彐', '彡', '彳', '心', '戈', '戶', '手',
+    '支', '攴', '文', '斗', '斤', '方', '无', '日', '曰', '月', '木', '欠', '止', '歹', '殳', '毋',
+    '比', '毛', '氏', '气', '水', '火', '爪', '父', '爻', '爿', '片', '牙', '牛', '犬', '玄', '玉',
+    '瓜', '瓦', '甘', '生', '用', '田', '疋', '疒', '癶', '白', '皮', '皿', '目', '矛', '矢', '石',
+    '示', '禸', '禾', '穴', '立', '竹', '米', '糸', '缶', '网', '羊', '羽', '老', '而', '耒', '耳',
+    '聿', '肉', '臣', '自', '至', '臼', '舌', '舛', '舟', '艮', '色', '艸', '虍', '虫', '血', '行',
+    '衣', '襾', '見', '角', '言', '谷', '豆', '豕', '豸', '貝', '赤', '走', '足', '身', '車', '辛',
+    '辰', '辵', '邑', '酉', '釆', '里', '金', '長', '門', '阜', '隶', '隹', '雨', '靑', '非', '面',
+    '革', '韋', '韭', '音', '頁', '風', '飛', '食', '首', '香', '馬', '骨', '高', '髟', '鬥', '鬯',
+    '鬲', '鬼', '魚', '鳥', '鹵', '鹿', '麥', '麻', '黃', '黍', '黑', '黹', '黽', '鼎', '鼓', '鼠',
+    '鼻', '齊', '齒', '龍', '龜', '龠',
+];
+
+/// CJK Radicals Supplement forms (U+2E80 - U+2EFF) with a unified ideograph of the same shape
+const SUPPLEMENT: &[(char, char)] = &[
+    ('⺁', '厂'),
+    ('⺂', '乛'),
+    ('⺃', '乚'),
+    ('⺄', '乙'),
+    ('⺅', '亻'),
+    ('⺆', '冂'),
+    ('⺉', '刂'),
+    ('⺊', '卜'),
+    ('⺋', '㔾'),
+    ('⺎', '兀'),
+    ('⺏', '尣'),
+    ('⺐', '尢'),
+    ('⺒', '巳'),
+    ('⺓', '幺'),
+    ('⺔', '彑'),
+    ('⺖', '忄'),
+    ('⺗', '㣺'),
+    ('⺘', '扌'),
+    ('⺙', '攵'),
+    ('⺛', '旡'),
+    ('⺜', '日'),
+    ('⺝', '月'),
+    ('⺞', '歺'),
+    ('⺟', '母'),
+    ('⺠', '民'),
+    ('⺡', '氵'),
+    ('⺢', '氺'),
+    ('⺣', '灬'),
+    ('⺤', '爫'),
+    ('⺦', '丬'),
+    ('⺧', '牛'),
+    ('⺨', '犭'),
+    ('⺩', '王'),
+    ('⺪', '疋'),
+    ('⺫', '罒'),
+    ('⺬', '示'),
+    ('⺭', '礻'),
+    ('⺮', '𥫗'),
+    ('⺯', '糹'),
+    ('⺰', '纟'),
+    ('⺲', '罒'),
+    ('⺳', '罓'),
+    ('⺸', '羋'),
+    ('⺹', '耂'),
+    ('⺺', '肀'),
+    ('⺻', '聿'),
+    ('⺼', '肉'),
+    ('⺽', '𦥑'),
+    ('⺾', '艹'),
+    ('⺿', '艹'),
+    ('⻀', '艹'),
+    ('⻁', '虎'),
+    ('⻂', '衤'),
+    ('⻃', '覀'),
+    ('⻄', '西'),
+    ('⻅', '见'),
+    ('⻆', '角'),
+    ('⻇', '𧢲'),
+    ('⻈', '讠'),
+    ('⻉', '贝'),
+    ('⻊', '𧾷'),
+    ('⻋', '车'),
+    ('⻌', '辶'),
+    ('⻍', '辶'),
+    ('⻎', '辶'),
+    ('⻏', '阝'),
+    ('⻐', '钅'),
+    ('⻑', '長'),
+    ('⻒', '镸'),
+    ('⻓', '长'),
+    ('⻔', '门'),
+    ('⻕', '𨸏'),
+    ('⻖', '阝'),
+    ('⻗', '雨'),
+    ('⻘', '青'),
+    ('⻙', '韦'),
+    ('⻚', '页'),
+    ('⻛', '风'),
+    ('⻜', '飞'),
+    ('⻝', '食'),
+    ('⻟', '飠'),
+    ('⻠', '饣'),
+    ('⻢', '马'),
+    ('⻣', '骨'),
+    ('⻤', '鬼'),
+    ('⻥', '鱼'),
+    ('⻦', '鸟'),
+    ('⻧', '卤'),
+    ('⻨', '麦'),
+    ('⻩', '黄'),
+    ('⻪', '黾'),
+    ('⻫', '斉'),
+    ('⻬', '齐'),
+    ('⻭', '歯'),
+    ('⻮', '齿'),
+    ('⻯', '竜'),
+    ('⻰', '龙'),
+    ('⻱', '龜'),
+    ('⻲', '亀'),
+    ('⻳', '龟'),
+];
+
+const KANGXI_START: u32 = 0x2F00;
+
+/// Returns the unified ideograph of a Kangxi radical or CJK Radicals Supplement form, eg. '口'
+/// for '⼝' (U+2F1D). Other characters get returned as they are.
+#[inline]
+pub fn to_unified(c: char) -> char {
+    if let Some(unified) = (c as u32)
+        .checked_sub(KANGXI_START)
+        .and_then(|i| KANGXI.get(i as usize))
+    {
+        return *unified;
+    }
+    SUPPLEMENT
+        .iter()
+        .find(|i| i.0 == c)
+        .map(|i| i.1)
+        .unwrap_or(c)
+}
+
+/// Returns the radical code point of the unified ideograph `c`. Kangxi radicals are preferred
+/// over CJK Radicals Supplement forms.
+#[inline]
+pub fn to_radical(c: char) -> Option<char> {
+    if let Some(pos) = KANGXI.iter().position(|i| *i == c) {
+        return char::from_u32(KANGXI_START + pos as u32);
+    }
+    SUPPLEMENT.iter().find(|i| i.1 == c).map(|i| i.0)
+}
+
+/// Returns `true` if `c` is a Kangxi radical or a CJK Radicals Supplement form
+#[inline]
+pub fn is_radical(c: char) -> bool {
+    matches!(c, '\u{2E80}'..='\u{2EFF}' | '\u{2F00}'..='\u{2FDF}')
+}
+
+/// Replaces all radicals in `s` with their unified ideographs
+#[inline]
+pub fn normalize(s: &str) -> String {
+    s.chars().map(to_unified).collect()
+}
+
+/// Returns an iterator over all radicals with a unified ideograph together with this ideograph
+pub fn pairs() -> impl Iterator<Item = (char, char)> {
+    KANGXI
+        .iter()
+        .enumerate()
+        .filter_map(|(i, unified)| Some((char::from_u32(KANGXI_START + i as u32)?, *unified)))
+        .chain(SUPPLEMENT.iter().copied())
+}
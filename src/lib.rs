@@ -1,57 +1,67 @@
+pub mod binary;
+pub mod component_index;
 pub mod composition;
+pub mod database;
+pub mod decompose;
 pub mod destr_form;
 pub mod error;
 pub mod ids;
+pub mod kangxi;
 pub mod origin;
+pub mod origin_set;
+pub mod preference;
+pub mod pua_map;
+pub mod query;
+pub mod radical_map;
 pub mod sepecial_components;
+pub mod similarity;
+pub mod statistics;
+pub mod tree;
+pub mod unencoded;
 pub mod utils;
+pub mod variant_graph;
 pub mod xref;
 
+pub use binary::IdsBlob;
+pub use component_index::{ComponentFilter, ComponentIndex, ComponentQuery};
 pub use composition::{Composition, CompositionPart};
+pub use database::{IdsDatabase, LoadMode};
+pub use decompose::DecomposeOptions;
 pub use destr_form::DestructionForm;
+pub use error::ParseError;
 pub use ids::IDS;
 pub use origin::Origin;
+pub use origin_set::OriginSet;
+pub use preference::{RegionPreference, Selection, SelectionReason};
+pub use pua_map::PuaMap;
+pub use query::{IdsQuery, Pattern};
+pub use radical_map::{Placement, RadicalMap};
+pub use similarity::EditCosts;
+pub use statistics::Statistics;
+pub use tree::IdsNode;
+pub use unencoded::{UnencodedComponent, UnencodedTable};
+pub use variant_graph::VariantGraph;
 pub use xref::XRef;
 
-const MAPPINGS: &[(char, char)] = &[
-    ('牛', '牜'),
-    ('玉', '𤣩'),
-    ('竹', '𥫗'),
-    ('艸', '艹'),
-    //('肉', '月'),
-    ('糸', '糹'),
-    ('言', '訁'),
-    ('金', '釒'),
-    ('食', '飠'),
-    ('孑', '子'),
-    ('⺶', '羊'),
-];
-
-/// Maps radicals to the respective radical/glyph used in the ids dataset
+/// Maps radicals to the respective radical/glyph used in the ids dataset, using the built in
+/// `RadicalMap` without regard to their placement
 #[inline]
 pub fn map_special_form(inp: char) -> char {
-    MAPPINGS
-        .iter()
-        .find(|i| i.1 == inp)
-        .map(|i| i.0)
-        .unwrap_or(inp)
+    RadicalMap::standard().fold(inp, Placement::Any)
 }
 
 #[cfg(test)]
 mod test {
-    use std::{
-        fs::File,
-        io::{BufRead, BufReader},
-        str::FromStr,
-    };
+    use std::{collections::HashSet, str::FromStr};
 
     use crate::{
-        composition::{Composition, CompositionPart},
+        composition::{Annotation, Composition, CompositionPart, Modifier},
         destr_form::DestructionForm,
+        error::{ErrorKind, Field, LoadError},
         ids::IDS,
         origin::Origin,
         sepecial_components::is_special,
-        xref::{RefType, XRefItem},
+        xref::{RefType, XRefItem, XRefLink},
     };
 
     use super::*;
@@ -68,21 +78,61 @@ mod test {
 
     #[test]
     fn test_all() {
-        let reader = BufReader::new(File::open("./IDS.TXT").unwrap());
-        for line in reader.lines() {
-            let line = line.unwrap().trim().to_string();
-            if line.starts_with('#') || line.starts_with('\u{feff}') {
+        if let Err(err) = IdsDatabase::from_path("./IDS.TXT", LoadMode::Strict) {
+            panic!("{err}");
+        }
+
+        // Every line of the whole file gets formatted back as it was written
+        let text = std::fs::read_to_string("./IDS.TXT").unwrap();
+        for (nr, line) in text.lines().enumerate() {
+            let line = line.trim_start_matches('\u{feff}').trim();
+            if line.is_empty() || line.starts_with('#') {
                 continue;
             }
+            let ids = IDS::from_str(line).unwrap();
+            assert_eq!(ids.to_string(), line, "line {}", nr + 1);
+        }
+    }
 
-            let parsed = IDS::from_str(&line);
-            if let Err(err) = parsed {
-                panic!("{line:?}: {err}");
-            }
+    #[test]
+    fn test_round_trip_all() {
+        let text = include_str!("../tests/fixtures/IDS.sample.txt");
+        let db = IdsDatabase::from_reader(text.as_bytes(), LoadMode::Strict).unwrap();
+
+        let lines: Vec<&str> = text
+            .lines()
+            .filter(|i| !i.is_empty() && !i.starts_with('#'))
+            .collect();
+        assert_eq!(db.len(), lines.len());
+
+        // Every line is reproduced as it was written, radical variants included
+        for (ids, line) in db.iter().zip(lines) {
+            assert_eq!(ids.to_string(), line);
+            assert_eq!(IDS::from_str(line).as_ref(), Ok(ids), "{line}");
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let lines = [
+            "U+9AD8	高	^⿳亠口冋$(GHJKTV)	*U+507D≡U+50DE",
+            "U+4E12	丒	^⿱刃一$(GT)	^⿱⿹𠃌㐅一$(J)",
+            "U+8C61	象	^⿱{02}𧰨$(GHJKTV)",
+            "U+20120	𠄠	^⿱一⿱一一$(G)	*U+4E8C≠U+2011E(G)",
+            "U+4E12	丒	^⿰↔口？$",
+            "U+4E12	丒	^⿱刃一$(GJ[TV])	^⿱⿹𠃌㐅一$([J]) unverified",
+        ];
+        for line in lines {
+            assert_eq!(IDS::from_str(line).unwrap().to_string(), line);
         }
+
+        // Origins are written in their canonical order
+        let composition = Composition::from_str("^⿱刃一$(T[V]GJ)").unwrap();
+        assert_eq!(composition.to_string(), "^⿱刃一$(GJT[V])");
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_dec_ids_full() {
         let input = "U+9AD8	高	^⿳亠口冋$(GHJKTV)	*U+507D≡U+50DE";
         let parsed = IDS::from_str(input);
@@ -91,17 +141,21 @@ mod test {
         assert_eq!(
             parsed.unwrap(),
             IDS {
+                code_point: 0x9AD8,
                 literal: '高',
+                sequence: None,
                 xrefs: vec![XRef {
-                    ref_type: RefType::UnifiableVariants,
-                    left: XRefItem {
+                    head: XRefItem {
                         literal: '偽',
                         src_identifier: None,
                     },
-                    right: XRefItem {
-                        literal: '僞',
-                        src_identifier: None,
-                    },
+                    links: vec![XRefLink {
+                        ref_type: RefType::UnifiableVariants,
+                        item: XRefItem {
+                            literal: '僞',
+                            src_identifier: None,
+                        },
+                    }],
                 }],
                 compositions: vec![Composition {
                     data: vec![
@@ -110,14 +164,24 @@ mod test {
                         CompositionPart::Radical('口'),
                         CompositionPart::Radical('冋'),
                     ],
-                    reg_origins: vec![
+                    tree: IdsNode::Operator(
+                        DestructionForm::Horizontally3,
+                        vec![
+                            IdsNode::Leaf(CompositionPart::Radical('亠')),
+                            IdsNode::Leaf(CompositionPart::Radical('口')),
+                            IdsNode::Leaf(CompositionPart::Radical('冋')),
+                        ]
+                    ),
+                    bracketed_origins: OriginSet::new(),
+                    note: None,
+                    reg_origins: OriginSet::from([
                         Origin::China,
                         Origin::HongKong,
                         Origin::Japan,
                         Origin::SouthKorea,
                         Origin::Taiwan,
                         Origin::Vietnam,
-                    ]
+                    ])
                 }]
             }
         )
@@ -136,7 +200,63 @@ mod test {
         let input = "U+4E8C	二	^⿱一一$(GHJKTV)	*U+4E8C≠U+2011E≠U+2011F≠U+20120";
         let parsed = IDS::from_str(input);
         assert!(parsed.is_ok());
-        // TODO: add full test
+
+        let item = |literal| XRefItem {
+            literal,
+            src_identifier: None,
+        };
+        let link = |literal| XRefLink {
+            ref_type: RefType::FullNoCognate,
+            item: item(literal),
+        };
+        assert_eq!(
+            parsed.unwrap().xrefs,
+            vec![XRef {
+                head: item('二'),
+                links: vec![link('𠄞'), link('𠄟'), link('𠄠')],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_xref_chain() {
+        let xref = XRef::from_str("U+5098(V)=U+4EE8≡U+5098").unwrap();
+        let pairs = xref
+            .pairs()
+            .map(|(l, rt, r)| (l.literal, rt, r.literal))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            vec![
+                ('傘', RefType::Full, '仨'),
+                ('仨', RefType::UnifiableVariants, '傘')
+            ]
+        );
+        assert_eq!(xref.items().count(), 3);
+        assert_eq!(xref.to_string(), "U+5098(V)=U+4EE8≡U+5098");
+
+        for (input, column) in [("U+5098", 0), ("U+5098≠U+XYZ", 7), ("U+5098≠U+4E8C≠", 14)] {
+            let err = XRef::from_str(input).unwrap_err();
+            assert_eq!(err.kind, ErrorKind::InvalidXRef, "{input}");
+            assert_eq!(err.column, Some(column), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_xref_errors() {
+        let input = "U+4E8C	二	^⿱一一$(GHJKTV)	*U+4E8C≠U+XYZ";
+        let err = IDS::from_str(input).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidXRef);
+        assert_eq!(err.field, Some(Field::XRef));
+
+        let (parsed, warnings) = IDS::from_str_lenient(input).unwrap();
+        assert!(parsed.xrefs.is_empty());
+        assert_eq!(warnings.len(), 1);
+
+        let db = IdsDatabase::from_reader(input.as_bytes(), LoadMode::Lenient).unwrap();
+        assert_eq!(db.len(), 1);
+        assert!(db.errors().is_empty());
+        assert_eq!(db.warnings()[0].line, Some(1));
     }
 
     #[test]
@@ -165,6 +285,20 @@ mod test {
                 src_identifier: Some(Origin::Vietnam)
             }
         );
+
+        let item = XRefItem::from_str("U+5098(KP)").unwrap();
+        assert_eq!(item.src_identifier, Some(Origin::NordKorea));
+        assert_eq!(XRefItem::from_str(&item.to_string()).unwrap(), item);
+
+        for (input, kind, column) in [
+            ("U+5098(VQ)", ErrorKind::InvalidOrigin('V'), 7),
+            ("U+5098(V", ErrorKind::InvalidXRef, 6),
+            ("U+5098()", ErrorKind::InvalidXRef, 6),
+            ("U+5098(V)(J)", ErrorKind::InvalidOrigin('V'), 7),
+        ] {
+            let err = XRefItem::from_str(input).unwrap_err();
+            assert_eq!((err.kind, err.column), (kind, Some(column)), "{input}");
+        }
     }
 
     #[test]
@@ -199,24 +333,24 @@ mod test {
     #[test]
     fn test_dec_composition1() {
         let input = "^⿳亠口冋$(GHJKTV)";
-        let parsed = Composition::from_str(&input);
+        let parsed = Composition::from_str(input);
 
         assert!(parsed.is_ok());
         let parsed = parsed.unwrap();
         assert_eq!(
             parsed.reg_origins,
-            vec![
+            OriginSet::from([
                 Origin::China,
                 Origin::HongKong,
                 Origin::Japan,
                 Origin::SouthKorea,
                 Origin::Taiwan,
                 Origin::Vietnam,
-            ]
+            ])
         );
 
         assert_eq!(
-            parsed.data,
+            parsed.data(),
             vec![
                 CompositionPart::Destructive(DestructionForm::Horizontally3),
                 CompositionPart::Radical('亠'),
@@ -225,4 +359,1215 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn test_composition_tree() {
+        let parsed = Composition::from_str("^⿱⿹𠃌㐅一$(J)").unwrap();
+        assert_eq!(
+            parsed.tree,
+            IdsNode::Operator(
+                DestructionForm::Horizontally,
+                vec![
+                    IdsNode::Operator(
+                        DestructionForm::BoxOpenBottomLeft,
+                        vec![
+                            IdsNode::Leaf(CompositionPart::Radical('𠃌')),
+                            IdsNode::Leaf(CompositionPart::Radical('㐅')),
+                        ]
+                    ),
+                    IdsNode::Leaf(CompositionPart::Radical('一')),
+                ]
+            )
+        );
+        assert_eq!(IdsNode::from_parts(&parsed.data()).unwrap(), parsed.tree);
+    }
+
+    #[test]
+    fn test_composition_tree_modifier() {
+        let parsed = Composition::from_str("^⿰↔口{02}$(G)").unwrap();
+        assert_eq!(
+            parsed.tree,
+            IdsNode::Operator(
+                DestructionForm::Vertically,
+                vec![
+                    IdsNode::Modified(
+                        Modifier::Mirror,
+                        Box::new(IdsNode::Leaf(CompositionPart::Radical('口')))
+                    ),
+                    IdsNode::Leaf(CompositionPart::UnencodedComponent(2)),
+                ]
+            )
+        );
+        assert_eq!(
+            parsed.data(),
+            vec![
+                CompositionPart::Destructive(DestructionForm::Vertically),
+                CompositionPart::Modifier(Modifier::Mirror),
+                CompositionPart::Radical('口'),
+                CompositionPart::UnencodedComponent(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_composition_arity() {
+        let cases = [
+            (
+                "^⿰口$(G)",
+                ErrorKind::MissingOperand(DestructionForm::Vertically),
+            ),
+            (
+                "^⿲口口$(G)",
+                ErrorKind::MissingOperand(DestructionForm::Vertically3),
+            ),
+            ("^⿱一二三$(G)", ErrorKind::TrailingComponents(1)),
+            ("^口口$(G)", ErrorKind::TrailingComponents(1)),
+            ("^⿰口↔$(G)", ErrorKind::DanglingModifier(Modifier::Mirror)),
+            ("^$(G)", ErrorKind::EmptyComposition),
+        ];
+
+        for (input, kind) in cases {
+            let err = Composition::from_str(input).unwrap_err();
+            assert_eq!(err.kind, kind, "{input}");
+        }
+
+        assert!(Composition::from_str("^⿲口口口$(G)").is_ok());
+        assert!(Composition::from_str("^⿰？口$(G)").is_ok());
+    }
+
+    #[test]
+    fn test_error_position() {
+        let input = "U+4E12	丒	^⿱刃一$(GT)	^⿱⿹𠃌㐅$(J)";
+        let err = IDS::from_str(input).unwrap_err().on_line(42);
+        assert_eq!(
+            err.kind,
+            ErrorKind::MissingOperand(DestructionForm::Horizontally)
+        );
+        assert_eq!(err.field, Some(Field::Composition));
+        assert_eq!(err.column, Some(5));
+        assert_eq!(err.input.as_deref(), Some("^⿱⿹𠃌㐅$(J)"));
+        assert_eq!(
+            err.to_string(),
+            "line 42: composition field: missing operand for ⿱ at column 6
+    ^⿱⿹𠃌㐅$(J)
+             ^"
+        );
+
+        let err = Composition::from_str("^⿰{x}口$(G)").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnexpectedCharacter);
+        assert_eq!(err.column, Some(2));
+
+        // The first character is column 1 in messages
+        let err = ParseError::new(ErrorKind::InvalidCodePoint).at(0);
+        assert_eq!(err.to_string(), "invalid code point at column 1");
+        let err = ParseError::new(ErrorKind::UnexpectedEnd).at(8);
+        assert_eq!(err.to_string(), "unexpected end of data at byte 8");
+
+        let err = XRef::from_str("U+507D≡U+50DE(Q)").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidOrigin('Q'));
+        assert_eq!(err.field, Some(Field::XRef));
+        assert_eq!(err.column, Some(14));
+    }
+
+    #[test]
+    fn test_database() {
+        let input = "\u{feff}# IDS.TXT\n\
+            #\tcomment\n\
+            \n\
+            U+9AD8\t高\t^⿳亠口冋$(GHJKTV)\n\
+            U+4E12\t丒\t^⿱刃一$(GT)\t^⿱⿹𠃌㐅$(J)\n\
+            U+8C61\t象\t^⿱{02}𧰨$(GHJKTV)\r\n";
+
+        let err = IdsDatabase::from_reader(input.as_bytes(), LoadMode::Strict).unwrap_err();
+        assert!(matches!(
+            err,
+            LoadError::Parse(ParseError { line: Some(5), .. })
+        ));
+
+        let db = IdsDatabase::from_reader(input.as_bytes(), LoadMode::Lenient).unwrap();
+        assert_eq!(db.len(), 2);
+        assert_eq!(db.errors().len(), 1);
+        assert_eq!(db.errors()[0].line, Some(5));
+        assert_eq!(db.get('高').unwrap().literal, '高');
+        assert_eq!(db.get_by_code_point(0x8C61).unwrap().literal, '象');
+        assert!(db.get('丒').is_none());
+    }
+
+    #[test]
+    fn test_component_index() {
+        let db: IdsDatabase = [
+            "U+9AD8\t高\t^⿳亠口冋$(GHJKTV)",
+            "U+5411\t向\t^⿵⿰丿冂口$(GHJKTV)",
+            "U+5426\t否\t^⿱不口$(GHTV)\t^⿱丆口$(J)",
+            "U+65E5\t日\t^日$(GHJKTV)",
+            "U+660E\t明\t^⿰日月$(GHJKTV)",
+            "U+9E7F\t鹿\t^⿸{55}比$(GHJKTV)",
+        ]
+        .iter()
+        .map(|i| IDS::from_str(i).unwrap())
+        .collect();
+        let index = db.component_index();
+
+        let top = ComponentFilter::default();
+        let nested = ComponentFilter {
+            nested: true,
+            ..Default::default()
+        };
+
+        assert_eq!(index.find('口', top), vec!['向', '否', '高']);
+        assert_eq!(index.find('冂', top), Vec::<char>::new());
+        assert_eq!(index.find('冂', nested), vec!['向']);
+        assert_eq!(index.find('\u{F2D0}', top), vec!['鹿']);
+        assert!(index.find('日', nested).contains(&'明'));
+        assert!(!index.find('日', nested).contains(&'日'));
+
+        let japanese = ComponentFilter {
+            origin: Some(Origin::Japan),
+            ..Default::default()
+        };
+        assert_eq!(index.find('不', japanese), Vec::<char>::new());
+        assert_eq!(index.find('丆', japanese), vec!['否']);
+
+        let query = ComponentQuery {
+            all_of: vec!['口', '丿'],
+            none_of: vec!['亠'],
+            filter: nested,
+            ..Default::default()
+        };
+        assert_eq!(index.query(&query), vec!['向']);
+
+        let query = ComponentQuery {
+            any_of: vec!['月', '比'],
+            ..Default::default()
+        };
+        assert_eq!(index.query(&query), vec!['明', '鹿']);
+
+        let query = ComponentQuery {
+            none_of: vec!['口', '日'],
+            ..Default::default()
+        };
+        assert_eq!(index.query(&query), vec!['日', '鹿']);
+    }
+
+    #[test]
+    fn test_decompose() {
+        let db: IdsDatabase = [
+            "U+9AD8\t高\t^⿳亠口冋$(GHJKTV)",
+            "U+4EA0\t亠\t^亠$(GHJKTV)",
+            "U+53E3\t口\t^口$(GHJKTV)",
+            "U+5182\t冂\t^冂$(GHJKTV)",
+            "U+518B\t冋\t^⿵冂口$(GHJKTV)",
+            "U+4E12\t丒\t^⿱刃一$(GT)\t^⿱⿹𠃌㐅一$(J)",
+            "U+5203\t刃\t^⿻刀丶$(GHJKTV)",
+            "U+4E00\t一\t^一$(GHJKTV)",
+            // Cyclic entries
+            "U+5F73\t彳\t^⿰彡亍$(G)",
+            "U+5F61\t彡\t^⿰彳丿$(G)",
+        ]
+        .iter()
+        .map(|i| IDS::from_str(i).unwrap())
+        .collect();
+
+        let leaf = |c| IdsNode::Leaf(CompositionPart::Radical(c));
+
+        assert_eq!(
+            db.decompose('高', DecomposeOptions::default()),
+            Some(IdsNode::Operator(
+                DestructionForm::Horizontally3,
+                vec![
+                    leaf('亠'),
+                    leaf('口'),
+                    IdsNode::Operator(DestructionForm::BoxOpenBottom, vec![leaf('冂'), leaf('口')])
+                ]
+            ))
+        );
+        assert!(db.get('亠').unwrap().is_atomic());
+        assert!(db.decompose('丁', DecomposeOptions::default()).is_none());
+
+        let mut options = DecomposeOptions {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            db.decompose_components('高', options),
+            vec!['亠', '口', '冋']
+        );
+
+        options.max_depth = None;
+        assert_eq!(
+            db.decompose_components('丒', options),
+            vec!['刀', '丶', '一']
+        );
+        options.preference = Origin::Japan.into();
+        assert_eq!(
+            db.decompose_components('丒', options),
+            vec!['𠃌', '㐅', '一']
+        );
+
+        options.preference = RegionPreference::default();
+        assert_eq!(
+            db.decompose_components('彳', options),
+            vec!['彳', '丿', '亍']
+        );
+    }
+
+    #[test]
+    fn test_statistics() {
+        let db: IdsDatabase = [
+            "U+9AD8\t高\t^⿳亠口冋$(GHJKTV)",
+            "U+4EA0\t亠\t^亠$(GHJKTV)",
+            "U+53E3\t口\t^口$(GHJKTV)",
+            "U+5182\t冂\t^冂$(GHJKTV)",
+            "U+518B\t冋\t^⿵冂口$(GHJKTV)",
+            "U+4E12\t丒\t^⿱刃一$(GT)\t^⿱⿹𠃌㐅一$(J)\t^⿱刃二$(X)",
+            "U+5203\t刃\t^⿻刀丶$(GHJKTV)",
+            "U+4E00\t一\t^一$(GHJKTV)",
+            "U+200D1\t𠃑\t^⿱{01}乚$(G)\t^⿱〾口乚$(T)",
+        ]
+        .iter()
+        .map(|i| IDS::from_str(i).unwrap())
+        .collect();
+
+        let stats = db.statistics();
+        assert_eq!(stats.entries, 9);
+        assert_eq!(stats.compositions, 12);
+        assert_eq!(stats.component_count('口'), 3);
+        assert_eq!(stats.component_count('冂'), 1);
+        assert_eq!(stats.recursive_component_count('冂'), 2);
+        assert_eq!(stats.recursive_component_count('口'), 2);
+        assert_eq!(stats.components[0], (CompositionPart::Radical('口'), 3));
+        assert_eq!(stats.form_count(DestructionForm::Horizontally), 5);
+        assert_eq!(stats.form_count(DestructionForm::Horizontally3), 1);
+        assert_eq!(stats.origin_count(Origin::Japan), 8);
+        assert_eq!(stats.origin_count(Origin::Alternative), 1);
+        assert_eq!(stats.origin_count(Origin::UCS2003), 0);
+        assert_eq!(stats.regional_variants, vec!['丒', '𠃑']);
+        assert_eq!((stats.unencoded, stats.modifiers), (1, 1));
+
+        // Components are counted the same way as by the `ComponentIndex`
+        let index = db.component_index();
+        for (component, count) in &stats.components {
+            let found = index.find_part(*component, ComponentFilter::default());
+            assert_eq!(found.len(), *count, "{component}");
+        }
+
+        let top = stats.truncated(1);
+        assert_eq!(top.components.len(), 1);
+        assert_eq!(top.forms, stats.forms);
+        assert!(top.to_string().contains("\nforms:\n  ⿱ 5\n"));
+        assert_eq!(Statistics::default().origin_count(Origin::China), 0);
+
+        // Both lists fold variant radicals the same way
+        let db: IdsDatabase = ["U+82B1\t花\t^⿱艹化$(G)", "U+8349\t草\t^⿱艹早$(G)"]
+            .iter()
+            .map(|i| IDS::from_str(i).unwrap())
+            .collect();
+        let stats = db.statistics();
+        let grass = CompositionPart::Radical('艸');
+        assert_eq!(stats.components[0], (grass, 2));
+        assert_eq!(stats.recursive_components[0], (grass, 2));
+        assert_eq!(stats.component_count('艹'), 2);
+        assert_eq!(stats.recursive_component_count('艹'), 2);
+    }
+
+    #[test]
+    fn test_region_preference() {
+        let ids = IDS::from_str("U+4E12\t丒\t^⿱刃一$(GT)\t^⿱⿹𠃌㐅一$(J)\t^⿱刃二$(X)").unwrap();
+
+        let preference = RegionPreference::from_locale("ja").unwrap();
+        assert_eq!(preference.to_string(), "J,T,H");
+        let selection = ids.select(&preference).unwrap();
+        assert_eq!(selection.composition, &ids.compositions[1]);
+        assert_eq!(
+            selection.reason,
+            SelectionReason::Preferred {
+                origin: Origin::Japan,
+                rank: 0
+            }
+        );
+
+        let preference = RegionPreference::from_str("K, T").unwrap();
+        let selection = ids.select(&preference).unwrap();
+        assert_eq!(selection.composition, &ids.compositions[0]);
+        assert_eq!(
+            selection.reason,
+            SelectionReason::Preferred {
+                origin: Origin::Taiwan,
+                rank: 1
+            }
+        );
+
+        let preference = RegionPreference::from(Origin::Vietnam);
+        let selection = ids.select(&preference).unwrap();
+        assert_eq!(selection.composition, &ids.compositions[0]);
+        assert_eq!(selection.reason, SelectionReason::Fallback);
+        assert!(ids.select(&preference.without_fallback()).is_none());
+
+        assert_eq!(
+            RegionPreference::from_locale("zh-Hant-HK")
+                .unwrap()
+                .origins(),
+            &[Origin::HongKong, Origin::Taiwan, Origin::Macau]
+        );
+        assert_eq!(
+            RegionPreference::from_locale("zh_TW").unwrap().origins()[0],
+            Origin::Taiwan
+        );
+        assert_eq!(
+            RegionPreference::from_locale("zh-Hans").unwrap().origins(),
+            &[Origin::China]
+        );
+        assert!(RegionPreference::from_locale("en-US").is_none());
+
+        let preference = RegionPreference::from_str("KP,P,J").unwrap();
+        assert_eq!(
+            preference,
+            RegionPreference::new([Origin::NordKorea, Origin::Japan])
+        );
+        let err = RegionPreference::from_str("J,Q").unwrap_err();
+        assert_eq!(
+            (err.kind, err.column),
+            (ErrorKind::InvalidOrigin('Q'), Some(2))
+        );
+        // Columns point at the code itself, not at the whitespace before it
+        let err = RegionPreference::from_str("J, Q").unwrap_err();
+        assert_eq!(
+            (err.kind, err.column),
+            (ErrorKind::InvalidOrigin('Q'), Some(3))
+        );
+        let err = RegionPreference::from_str("J,  Q").unwrap_err();
+        assert_eq!(
+            (err.kind, err.column),
+            (ErrorKind::InvalidOrigin('Q'), Some(4))
+        );
+    }
+
+    #[test]
+    fn test_unicode_15_1_forms() {
+        let cases = [
+            ('⿼', DestructionForm::BoxOpenLeft, 2),
+            ('⿽', DestructionForm::BoxOpenTopLeft, 2),
+            ('⿾', DestructionForm::Reflection, 1),
+            ('⿿', DestructionForm::Rotation, 1),
+            ('㇯', DestructionForm::Subtraction, 2),
+        ];
+        for (c, form, arity) in cases {
+            assert_eq!(DestructionForm::try_from(c), Ok(form));
+            assert_eq!(form.arity(), arity);
+            assert_eq!(char::from(form), c);
+        }
+
+        let parsed = Composition::from_str("^⿰⿾口㇯日一$(G)").unwrap();
+        assert_eq!(
+            parsed.tree,
+            IdsNode::Operator(
+                DestructionForm::Vertically,
+                vec![
+                    IdsNode::Operator(
+                        DestructionForm::Reflection,
+                        vec![IdsNode::Leaf(CompositionPart::Radical('口'))]
+                    ),
+                    IdsNode::Operator(
+                        DestructionForm::Subtraction,
+                        vec![
+                            IdsNode::Leaf(CompositionPart::Radical('日')),
+                            IdsNode::Leaf(CompositionPart::Radical('一')),
+                        ]
+                    ),
+                ]
+            )
+        );
+        assert_eq!(parsed.to_string(), "^⿰⿾口㇯日一$(G)");
+
+        let err = Composition::from_str("^⿰口⿿$(G)").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::MissingOperand(DestructionForm::Rotation)
+        );
+        assert!(Composition::from_str("^⿽口⿼日一$(G)").is_ok());
+    }
+
+    #[test]
+    fn test_bracketed_origins() {
+        let parsed = Composition::from_str("^⿱刃一$(G[TV]J) unverified").unwrap();
+        assert_eq!(
+            parsed.reg_origins,
+            OriginSet::from([
+                Origin::China,
+                Origin::Taiwan,
+                Origin::Vietnam,
+                Origin::Japan
+            ])
+        );
+        assert_eq!(
+            parsed.bracketed_origins,
+            OriginSet::from([Origin::Taiwan, Origin::Vietnam])
+        );
+        assert_eq!(parsed.note.as_deref(), Some(" unverified"));
+
+        assert_eq!(parsed.annotation(Origin::China), Some(Annotation::Plain));
+        assert_eq!(
+            parsed.annotation(Origin::Taiwan),
+            Some(Annotation::Bracketed)
+        );
+        assert_eq!(parsed.annotation(Origin::SouthKorea), None);
+        assert!(parsed.is_attested(Origin::Japan));
+        assert!(!parsed.is_attested(Origin::Vietnam));
+
+        let parsed = Composition::from_str("^⿱刃一$(GT)").unwrap();
+        assert!(parsed.bracketed_origins.is_empty());
+        assert!(parsed.note.is_none());
+
+        // Malformed origin lists are rejected
+        let cases = [
+            ("^口$(G", 5),
+            ("^口$(G]", 6),
+            ("^口$([G)", 4),
+            ("^口$()", 4),
+            ("^口$(G]T)", 5),
+            ("^口$([G[T]])", 6),
+            ("^口$(G[])", 6),
+        ];
+        for (input, column) in cases {
+            let err = Composition::from_str(input).unwrap_err();
+            assert_eq!(
+                (err.kind, err.column),
+                (ErrorKind::UnexpectedCharacter, Some(column)),
+                "{input}"
+            );
+        }
+
+        // Notes without an origin list are kept as well
+        let parsed = Composition::from_str("^口$X").unwrap();
+        assert!(parsed.reg_origins.is_empty());
+        assert_eq!(parsed.note.as_deref(), Some("X"));
+        assert_eq!(parsed.to_string(), "^口$X");
+    }
+
+    #[test]
+    fn test_code_point() {
+        let parsed = IDS::from_str("U+9AD8	高	^⿳亠口冋$(GHJKTV)").unwrap();
+        assert_eq!(parsed.code_point, 0x9AD8);
+        assert_eq!(parsed.sequence, None);
+
+        let cases = [
+            (
+                "U+9AD9	高	^⿳亠口冋$(GHJKTV)",
+                ErrorKind::CodePointMismatch(0x9AD9),
+                Field::Literal,
+            ),
+            (
+                "U+9AD8		^⿳亠口冋$(GHJKTV)",
+                ErrorKind::MissingLiteral,
+                Field::Literal,
+            ),
+            ("U+9AD8", ErrorKind::MissingLiteral, Field::Literal),
+            ("9AD8	高", ErrorKind::InvalidCodePoint, Field::CodePoint),
+            ("", ErrorKind::InvalidCodePoint, Field::CodePoint),
+        ];
+        for (input, kind, field) in cases {
+            let err = IDS::from_str(input).unwrap_err();
+            assert_eq!(err.kind, kind, "{input}");
+            assert_eq!(err.field, Some(field), "{input}");
+        }
+
+        let input = "U+8FBB	辻\u{E0100}	^⿺⻌十$(J)";
+        let parsed = IDS::from_str(input).unwrap();
+        assert_eq!(parsed.literal, '辻');
+        assert_eq!(parsed.sequence.as_deref(), Some("辻\u{E0100}"));
+        assert_eq!(parsed.to_string(), input);
+
+        let db: IdsDatabase = [input, "U+8FBB	辻	^⿺⻍十$(GHJKTV)"]
+            .iter()
+            .map(|i| IDS::from_str(i).unwrap())
+            .collect();
+        assert_eq!(db.len(), 2);
+        assert_eq!(db.get('辻').unwrap().sequence, None);
+        assert_eq!(db.get_sequence("辻").unwrap().sequence, None);
+        assert_eq!(
+            db.get_sequence("辻\u{E0100}").unwrap().literal_str(),
+            "辻\u{E0100}"
+        );
+
+        // Only a single variation selector may follow the literal
+        let cases = [
+            ("U+4E00\t一二\t^一$(G)", 1),
+            ("U+8FBB\t辻\u{E0100}\u{E0101}\t^⿺⻌十$(J)", 2),
+        ];
+        for (input, column) in cases {
+            let err = IDS::from_str(input).unwrap_err();
+            assert_eq!(
+                (err.kind, err.field, err.column),
+                (
+                    ErrorKind::UnexpectedCharacter,
+                    Some(Field::Literal),
+                    Some(column)
+                ),
+                "{input}"
+            );
+            let (parsed, warnings) = IDS::from_str_lenient(input).unwrap();
+            assert_eq!((parsed.sequence, warnings.len()), (None, 1), "{input}");
+        }
+
+        // Unknown fields aren't dropped silently
+        let input = "U+4E00\t一\t^一$(G)\tfoo";
+        let err = IDS::from_str(input).unwrap_err();
+        assert_eq!((err.kind, err.column), (ErrorKind::UnknownField, Some(16)));
+        let (parsed, warnings) = IDS::from_str_lenient(input).unwrap();
+        assert_eq!(parsed.to_string(), "U+4E00\t一\t^一$(G)");
+        assert_eq!(warnings, vec![err]);
+    }
+
+    #[test]
+    fn test_variant_graph() {
+        let db: IdsDatabase = [
+            "U+4E8C	二	^⿱一一$(GHJKTV)	*U+4E8C≠U+2011E≠U+2011F",
+            "U+5098	傘	^⿸𠆦十$(GHJKTV)	*U+5098=U+4EE8	*U+4EE8≡U+5159",
+            "U+5159	兙	^⿰儿十$(GHJKTV)	*U+5159≡U+5341",
+        ]
+        .iter()
+        .map(|i| IDS::from_str(i).unwrap())
+        .collect();
+        let graph = db.variant_graph();
+
+        assert_eq!(graph.identical_glyphs('傘'), vec!['仨']);
+        assert_eq!(graph.unifiable_variants('仨'), vec!['兙', '十']);
+        assert_eq!(graph.unifiable_variants('傘'), Vec::<char>::new());
+        assert_eq!(graph.component('十'), vec!['仨', '傘', '兙', '十']);
+        assert_eq!(
+            graph.components(),
+            vec![vec!['二', '𠄞', '𠄟'], vec!['仨', '傘', '兙', '十']]
+        );
+        assert_eq!(graph.neighbours('二'), &[('𠄞', RefType::FullNoCognate)]);
+        assert_eq!(graph.edges().count(), 5);
+        assert!(graph.to_dot().contains("\"仨\" -- \"傘\" [label=\"=\"];"));
+    }
+
+    #[test]
+    fn test_query_parse() {
+        let query = IdsQuery::from_str("⿰?{口|⿱日一|{02}}$(JT)").unwrap();
+        assert_eq!(
+            query.origins,
+            OriginSet::from([Origin::Japan, Origin::Taiwan])
+        );
+        assert_eq!(
+            query.pattern,
+            Pattern::Operator(
+                DestructionForm::Vertically,
+                vec![
+                    Pattern::AnyComponent,
+                    Pattern::Alternation(vec![
+                        Pattern::Component(CompositionPart::Radical('口')),
+                        Pattern::Operator(
+                            DestructionForm::Horizontally,
+                            vec![
+                                Pattern::Component(CompositionPart::Radical('日')),
+                                Pattern::Component(CompositionPart::Radical('一')),
+                            ]
+                        ),
+                        Pattern::Component(CompositionPart::UnencodedComponent(2)),
+                    ])
+                ]
+            )
+        );
+
+        let cases = [
+            (
+                "⿰?",
+                ErrorKind::MissingOperand(DestructionForm::Vertically),
+            ),
+            ("⿰??*", ErrorKind::TrailingComponents(1)),
+            ("⿰?{口|日", ErrorKind::UnexpectedCharacter),
+            ("⿰?{口|}", ErrorKind::EmptyComposition),
+            ("", ErrorKind::EmptyComposition),
+            ("?$(Q)", ErrorKind::InvalidOrigin('Q')),
+        ];
+        for (input, kind) in cases {
+            assert_eq!(IdsQuery::from_str(input).unwrap_err().kind, kind, "{input}");
+        }
+
+        // The origin suffix has to be of the exact format "$(…)"
+        let cases = [
+            ("?$(J", ErrorKind::UnexpectedCharacter, 4),
+            ("?$J)", ErrorKind::UnexpectedCharacter, 2),
+            ("?$", ErrorKind::UnexpectedCharacter, 2),
+            ("?$(J)T", ErrorKind::UnexpectedCharacter, 5),
+            ("?$(J(T)", ErrorKind::InvalidOrigin('('), 4),
+            ("?$(JQ)", ErrorKind::InvalidOrigin('Q'), 4),
+            ("?$(GUCS2003)", ErrorKind::InvalidOrigin('C'), 5),
+        ];
+        for (input, kind, column) in cases {
+            let err = IdsQuery::from_str(input).unwrap_err();
+            assert_eq!((err.kind, err.column), (kind, Some(column)), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_query_search() {
+        let db: IdsDatabase = [
+            "U+6C5F	江	^⿰氵工$(GHJKTV)",
+            "U+6D77	海	^⿰氵每$(GHJKTV)",
+            "U+6F22	漢	^⿰氵⿱廿⿻口夫$(GHJKTV)",
+            "U+660E	明	^⿰日月$(GHJKTV)",
+            "U+5426	否	^⿱不口$(GHTV)	^⿱丆口$(J)",
+            "U+82B1	花	^⿱艹化$(GHJKTV)",
+            "U+8349	草	^⿱艹早$(GHJKTV)",
+        ]
+        .iter()
+        .map(|i| IDS::from_str(i).unwrap())
+        .collect();
+
+        let search = |q: &str| {
+            db.search(&IdsQuery::from_str(q).unwrap())
+                .into_iter()
+                .map(|i| (i.literal, i.cost))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(search("⿰氵?"), vec![('江', 1), ('海', 1)]);
+        assert_eq!(search("⿰氵*"), vec![('江', 1), ('海', 1), ('漢', 5)]);
+        assert_eq!(search("⿱艹*"), vec![('花', 1), ('草', 1)]);
+        assert_eq!(search("⿰?{月|工}"), vec![('明', 1), ('江', 1)]);
+        assert_eq!(search("⿱?口"), vec![('否', 1)]);
+        assert_eq!(search("⿱丆口$(G)"), vec![]);
+        assert_eq!(search("⿱丆口$(J)"), vec![('否', 0)]);
+        assert_eq!(search("*"), search("*$()"));
+    }
+
+    #[test]
+    fn test_normalize() {
+        let normalized = |s: &str| {
+            let mut composition = Composition::from_str(s).unwrap();
+            composition.normalize();
+            composition.tree
+        };
+
+        assert_eq!(normalized("^⿰氵⿰木木$(G)"), normalized("^⿲氵木木$(J)"));
+        assert_eq!(normalized("^⿰⿰氵木木$(G)"), normalized("^⿲氵木木$(J)"));
+        assert_eq!(
+            normalized("^⿱一⿱口⿱口口$(G)"),
+            normalized("^⿳一口⿱口口$(J)")
+        );
+        assert_ne!(normalized("^⿰氵⿱木木$(G)"), normalized("^⿲氵木木$(J)"));
+        assert_eq!(
+            normalized("^⿳一口⿱口口$(J)").to_string(),
+            "⿱一⿱口⿱口口"
+        );
+
+        let node = IdsNode::Leaf(CompositionPart::Radical('艹'));
+        assert_eq!(
+            node.normalized(),
+            IdsNode::Leaf(CompositionPart::Radical('艸'))
+        );
+
+        let unique: HashSet<IdsNode> = ["^⿲氵木木$(G)", "^⿰氵⿰木木$(J)", "^⿰⿰氵木木$(T)"]
+            .iter()
+            .map(|i| normalized(i))
+            .collect();
+        assert_eq!(unique.len(), 1);
+
+        let g = Composition::from_str("^⿲氵木木$(G[T])").unwrap();
+        let j = Composition::from_str("^⿰氵⿰木木$(J)").unwrap();
+        assert_ne!(g, j);
+        assert_eq!(g.key(), j.key());
+        assert!(g.same_layout(&j));
+        assert!(!g.same_layout(&Composition::from_str("^⿰氵林$(G)").unwrap()));
+        let unique: HashSet<IdsNode> = [&g, &j].iter().map(|i| i.key()).collect();
+        assert_eq!(unique.len(), 1);
+
+        let mut ids =
+            IDS::from_str("U+6DCB	淋	^⿲氵木木$(G[T])	^⿰氵⿰木木$(JT)	^⿰氵林$(K)").unwrap();
+        ids.dedup_compositions();
+        assert_eq!(ids.compositions.len(), 2);
+        assert_eq!(
+            ids.compositions[0].reg_origins,
+            OriginSet::from([Origin::China, Origin::Taiwan, Origin::Japan])
+        );
+        assert!(ids.compositions[0].bracketed_origins.is_empty());
+    }
+
+    #[test]
+    fn test_tree_distance() {
+        let tree = |s: &str| Composition::from_str(s).unwrap().tree.normalized();
+        let costs = EditCosts::default();
+        let distance = |a: &str, b: &str| similarity::tree_distance(&tree(a), &tree(b), &costs);
+
+        assert_eq!(distance("^⿰氵工$", "^⿰氵工$"), 0);
+        assert_eq!(distance("^⿰氵⿰木木$", "^⿲氵木木$"), 0);
+        assert_eq!(distance("^⿰氵工$", "^⿰氵每$"), 2);
+        assert_eq!(distance("^⿰氵工$", "^⿱氵工$"), 1);
+        assert_eq!(distance("^⿰氵工$", "^⿱艹化$"), 5);
+        // Deleting the two leaves, turning the operator into a leaf and replacing the component
+        assert_eq!(distance("^⿰日月$", "^日$"), 5);
+        assert_eq!(distance("^⿰氵林$", "^⿰氵⿰木木$"), 4);
+
+        let custom = EditCosts {
+            replace_component: 10,
+            ..Default::default()
+        };
+        // Deleting and inserting the component is cheaper than replacing it
+        assert_eq!(
+            similarity::tree_distance(&tree("^⿰氵工$"), &tree("^⿰氵每$"), &custom),
+            2
+        );
+    }
+
+    #[test]
+    fn test_similar() {
+        let db: IdsDatabase = [
+            "U+6C5F	江	^⿰氵工$(GHJKTV)",
+            "U+6D77	海	^⿰氵每$(GHJKTV)",
+            "U+5DE5	工	^工$(GHJKTV)",
+            "U+7EA2	红	^⿰纟工$(GHJKTV)",
+            "U+7A7A	空	^⿱穴工$(GHJKTV)",
+            "U+6F22	漢	^⿰氵⿱廿⿻口夫$(GHJKTV)",
+        ]
+        .iter()
+        .map(|i| IDS::from_str(i).unwrap())
+        .collect();
+
+        let similar = db
+            .similar('江', 3, &EditCosts::default())
+            .into_iter()
+            .map(|i| (i.literal, i.distance))
+            .collect::<Vec<_>>();
+        assert_eq!(similar, vec![('海', 2), ('红', 2), ('空', 3)]);
+        assert!(db.similar('丁', 3, &EditCosts::default()).is_empty());
+
+        // Inserting an entry invalidates the cached trees
+        let mut db = db;
+        db.insert(IDS::from_str("U+6D77	海	^⿰氵工$(GHJKTV)").unwrap());
+        let closest = db.similar('江', 1, &EditCosts::default());
+        assert_eq!((closest[0].literal, closest[0].distance), ('海', 0));
+    }
+
+    #[test]
+    fn test_radical_map() {
+        let map = RadicalMap::standard();
+        assert_eq!(map.fold('艹', Placement::Any), '艸');
+        assert_eq!(map.fold('𥫗', Placement::Top), '竹');
+        // Radical blocks are left to `RadicalMap::with_radical_blocks`
+        assert_eq!(map.fold('⺮', Placement::Top), '⺮');
+        assert_eq!(map.fold('月', Placement::Any), '月');
+        assert_eq!(map.fold('月', Placement::Left), '肉');
+        assert_eq!(map.fold('月', Placement::Right), '月');
+        assert_eq!(map_special_form('糹'), '糸');
+        assert!(map.variants('肉').contains(&'月'));
+        assert_eq!(map.bases('艹'), vec!['艸']);
+
+        // Compatibility ideographs fold to their canonical decomposition and from there on
+        assert_eq!(map.fold('\u{F90A}', Placement::Any), '金');
+        assert_eq!(map.fold('\u{2F800}', Placement::Any), '丽');
+        assert_eq!(map.fold('\u{FA5D}', Placement::Any), '艸');
+        let compatibility = ('\u{F900}'..='\u{FAFF}').chain('\u{2F800}'..='\u{2FA1F}');
+        assert!(
+            compatibility
+                .filter(|i| map.fold(*i, Placement::Any) != *i)
+                .count()
+                > 1000
+        );
+
+        // Folding depends on the placement within the normalized tree
+        let tree = |s: &str| Composition::from_str(s).unwrap().tree;
+        assert_eq!(tree("^⿰月复$").to_string(), "⿰月复");
+        assert_eq!(tree("^⿰月复$").normalized().to_string(), "⿰肉复");
+        assert_eq!(tree("^⿰日月$").normalized().to_string(), "⿰日月");
+        assert_eq!(tree("^⿱月月$").normalized().to_string(), "⿱月月");
+        assert_eq!(
+            tree("^⿲月月月$").normalized(),
+            tree("^⿰月⿰月月$").normalized()
+        );
+        // Placement is taken from the original tree, not the restructured one
+        assert_eq!(tree("^⿲月月月$").normalized().to_string(), "⿰肉⿰月月");
+
+        // Parsing keeps radicals as written, only normalizing folds them
+        assert_eq!(tree("^⿰⾔⼝$").to_string(), "⿰⾔⼝");
+        assert_eq!(tree("^⿱艹化$").to_string(), "⿱艹化");
+        let composition = Composition::from_str("^⿰牜⿱艹化$(G)").unwrap();
+        assert_eq!(composition.get_radicals().collect::<String>(), "牜艹化");
+        let folded = Composition::parse_with("^⿰牜⿱艹化$(G)", map).unwrap();
+        assert_eq!(folded.get_radicals().collect::<String>(), "牛艸化");
+        assert_eq!(
+            tree("^⿰⾔⼝$").normalized_with(RadicalMap::with_radical_blocks()),
+            tree("^⿰言口$").normalized()
+        );
+
+        // Custom and disabled mappings
+        let custom = RadicalMap::from_str("# custom\n水 氵 left\n\n心 忄").unwrap();
+        assert_eq!(custom.fold('氵', Placement::Left), '水');
+        assert_eq!(custom.fold('氵', Placement::Top), '氵');
+        assert_eq!(custom.fold('忄', Placement::Top), '心');
+        assert_eq!(custom.to_string(), "水 氵 left\n心 忄 any\n");
+        assert_eq!(
+            tree("^⿰氵工$").normalized_with(&custom).to_string(),
+            "⿰水工"
+        );
+
+        let empty = RadicalMap::new();
+        let composition = Composition::parse_with("^⿱艹化$(G)", &empty).unwrap();
+        assert_eq!(composition.tree.to_string(), "⿱艹化");
+        assert_eq!(
+            composition.tree.normalized_with(&empty).to_string(),
+            "⿱艹化"
+        );
+        assert_eq!(composition.tree.normalized().to_string(), "⿱艸化");
+
+        let err = RadicalMap::from_str("水 氵\n心 忄 inside").unwrap_err();
+        assert_eq!((err.line, err.column), (Some(2), Some(4)));
+        let err = RadicalMap::from_str("水").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnexpectedCharacter);
+    }
+
+    #[test]
+    fn test_kangxi() {
+        assert_eq!(kangxi::to_unified('⼝'), '口');
+        assert_eq!(kangxi::to_unified('⿕'), '龠');
+        assert_eq!(kangxi::to_unified('⺡'), '氵');
+        assert_eq!(kangxi::to_unified('口'), '口');
+        assert_eq!(kangxi::to_radical('口'), Some('⼝'));
+        assert_eq!(kangxi::to_radical('氵'), Some('⺡'));
+        assert_eq!(kangxi::to_radical('江'), None);
+        assert!(kangxi::is_radical('⺡') && !kangxi::is_radical('氵'));
+        assert_eq!(kangxi::normalize("⿰⺡⼯"), "⿰氵工");
+
+        assert_eq!(kangxi::to_unified('⺮'), '𥫗');
+        assert_eq!(kangxi::to_unified('⺩'), '王');
+
+        // Radical blocks fold to their unified ideograph, which then folds like any other radical
+        let map = RadicalMap::with_radical_blocks();
+        for (radical, unified) in kangxi::pairs() {
+            let standard = RadicalMap::standard().fold(unified, Placement::Any);
+            assert_eq!(map.fold(radical, Placement::Any), standard, "{radical}");
+        }
+        assert_eq!(map.fold('⺮', Placement::Top), '竹');
+        assert_eq!(map.fold('⺩', Placement::Left), '王');
+
+        let composition = Composition::parse_with("^⿰⺡⼯$(G)", map).unwrap();
+        assert_eq!(composition.tree.to_string(), "⿰氵工");
+        // Folded further into the form used in the dataset
+        assert_eq!(map.fold('⺾', Placement::Any), '艸');
+        assert_eq!(
+            Composition::from_str("^⿰⺡⼯$(G)")
+                .unwrap()
+                .tree
+                .to_string(),
+            "⿰⺡⼯"
+        );
+
+        let db: IdsDatabase = ["U+53E3	口	^口$(GHJKTV)", "U+6C5F	江	^⿰氵工$(GHJKTV)"]
+            .iter()
+            .map(|i| IDS::from_str(i).unwrap())
+            .collect();
+        assert_eq!(db.get_normalized('⼝').map(|i| i.literal), Some('口'));
+        assert!(db.get('⼝').is_none());
+
+        let query = IdsQuery::parse_with("⿰⺡?", map).unwrap();
+        assert_eq!(db.search(&query).len(), 1);
+        assert!(db.search(&IdsQuery::from_str("⿰⺡?").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_unencoded_table() {
+        let builtin = UnencodedTable::builtin();
+        assert_eq!(builtin.len(), 121);
+        assert_eq!(builtin.get(2).unwrap().code_point, Some('\u{F0CF}'));
+        assert_eq!(builtin.by_code_point('\u{F0CF}').unwrap().number, 2);
+        assert!(builtin.get(122).is_none());
+        // The shipped side table covers the built in `PuaMap`
+        assert!(PuaMap::builtin()
+            .iter()
+            .all(|(nr, c)| builtin.get(nr).unwrap().code_point == Some(c)));
+
+        let side_table = "# nr\tpua\tstrokes\tids\tdescription\n\
+            {02}\t\t3\t⿱丶⿻一丿\tDot above a crossed stroke\n\
+            {150}\tU+E000\n\
+            {151}\t\t\t⿰口口";
+        let mut table = UnencodedTable::builtin();
+        table.extend(UnencodedTable::from_str(side_table).unwrap());
+        assert_eq!(table.len(), 123);
+
+        let component = table
+            .get_part(&CompositionPart::UnencodedComponent(2))
+            .unwrap();
+        assert_eq!(component.code_point, Some('\u{F0CF}'));
+        assert_eq!(component.strokes, Some(3));
+        assert_eq!(component.approx_ids.as_deref(), Some("⿱丶⿻一丿"));
+        assert_eq!(
+            component.description.as_deref(),
+            Some("Dot above a crossed stroke")
+        );
+        assert_eq!(table.get(150).unwrap().display_text(), "\u{E000}");
+        assert_eq!(table.get(151).unwrap().display_text(), "⿰口口");
+        assert_eq!(UnencodedComponent::new(200).display_text(), "{200}");
+
+        let err = UnencodedTable::from_str("{01}\tU+E000\n{02}\tX").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidCodePoint);
+        assert_eq!((err.line, err.column), (Some(2), Some(5)));
+        let err = UnencodedTable::from_str("02\t").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnexpectedCharacter);
+
+        // All side tables skip the same lines and report the same errors from a reader
+        let text = "\u{feff}# comment\n\n  # indented\n{01}\tU+E001\n{02}\tX";
+        let Err(LoadError::Parse(err)) = UnencodedTable::from_reader(text.as_bytes()) else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(err, UnencodedTable::from_str(text).unwrap_err());
+        assert_eq!((err.line, err.column), (Some(5), Some(5)));
+        let text = "\u{feff}# comment\n\n  # indented\n{01} U+E001\n";
+        assert_eq!(
+            PuaMap::from_reader(text.as_bytes())
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![(1, '\u{E001}')]
+        );
+        let text = "\u{feff}# comment\n\n  # indented\n肉 月 left\n";
+        assert_eq!(
+            RadicalMap::from_reader(text.as_bytes())
+                .unwrap()
+                .to_string(),
+            "肉 月 left\n"
+        );
+    }
+
+    #[test]
+    fn test_pua_map() {
+        let builtin = PuaMap::builtin();
+        assert_eq!(builtin.len(), 121);
+        assert_eq!(builtin.get(2), Ok('\u{F0CF}'));
+        assert_eq!(builtin.number_of('\u{F0CF}'), Some(2));
+        assert_eq!(
+            builtin.get(130).unwrap_err().kind,
+            ErrorKind::UnknownUnencodedComponent(130)
+        );
+        assert_eq!(builtin.display(130), PuaMap::REPLACEMENT);
+
+        let font = PuaMap::from_str("# other font\n{01} U+E001\n  {130}\tU+E082").unwrap();
+        assert_eq!(font.get(1), Ok('\u{E001}'));
+        assert_eq!(font.get(130), Ok('\u{E082}'));
+        assert!(font.get(2).is_err());
+        let font = font.with_fallback('□');
+        assert_eq!(font.get(2), Ok('□'));
+
+        let err = PuaMap::from_str("{01} U+E001\n{02}  E002").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidCodePoint);
+        assert_eq!((err.line, err.column), (Some(2), Some(6)));
+
+        // Remapping a character drops its old number and the other way round
+        let mut map = PuaMap::new();
+        map.insert(1, '\u{E001}');
+        map.insert(2, '\u{E002}');
+        map.insert(3, '\u{E001}');
+        map.insert(2, '\u{E003}');
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            vec![(2, '\u{E003}'), (3, '\u{E001}')]
+        );
+        assert_eq!(map.number_of('\u{E001}'), Some(3));
+        assert_eq!(map.number_of('\u{E002}'), None);
+        assert!(map.lookup(1).is_none());
+        map.insert(3, '\u{E001}');
+        assert_eq!(map.len(), 2);
+
+        let table = UnencodedTable::from_str("{01}\tU+E001\n{02}\t\t3").unwrap();
+        let from_table = PuaMap::from(&table);
+        assert_eq!(from_table.len(), 1);
+
+        // Unknown components don't vanish
+        let composition = Composition::from_str("^⿰{02}{130}$(G)").unwrap();
+        assert_eq!(
+            composition.get_radicals().collect::<Vec<_>>(),
+            vec!['\u{F0CF}', PuaMap::REPLACEMENT]
+        );
+        assert!(composition.get_radicals_with(PuaMap::builtin()).is_err());
+        assert_eq!(
+            composition.get_radicals_with(&font),
+            Ok(vec!['□', '\u{E082}'])
+        );
+
+        let db: IdsDatabase = [
+            "U+4E00\t一\t^一$(G)",
+            "U+4E01\t丁\t^⿰{130}口$(G)",
+            "U+4E02\t丂\t^⿱{140}口$(G)",
+            "U+4E03\t七\t^⿱{02}口$(G)",
+        ]
+        .iter()
+        .map(|i| IDS::from_str(i).unwrap())
+        .collect();
+        assert_eq!(
+            db.decompose_components('丁', DecomposeOptions::default()),
+            vec![PuaMap::REPLACEMENT, '口']
+        );
+
+        // Unknown components are kept apart by their number
+        let top = ComponentFilter::default();
+        let index = db.component_index();
+        let unknown = CompositionPart::UnencodedComponent(130);
+        assert_eq!(index.find_part(unknown, top), vec!['丁']);
+        assert_eq!(
+            index.find_part(CompositionPart::UnencodedComponent(140), top),
+            vec!['丂']
+        );
+        assert!(index.find(PuaMap::REPLACEMENT, top).is_empty());
+        assert_eq!(index.find('\u{F0CF}', top), vec!['七']);
+        let stats = db.statistics();
+        assert_eq!(
+            (
+                stats.part_count(unknown),
+                stats.recursive_part_count(unknown)
+            ),
+            (1, 1)
+        );
+        assert_eq!(stats.component_count(PuaMap::REPLACEMENT), 0);
+        assert!(stats.to_string().contains("\n  {130} 1\n"));
+    }
+
+    #[test]
+    fn test_binary() {
+        let lines = [
+            "U+53E3	口	^口$(GHJKTV)",
+            "U+6D77	海	^⿰氵每$(GJ[T])ko	^⿰氵⿱𠂉毋$(T)",
+            "U+5098	傘	^⿸𠆭⿻{02}十$(G)	*U+5098(V)≡U+4E98≠U+20000",
+            "U+6F22	漢︀	^⿰氵〾⿱廿⿻口夫$(J)",
+            "U+4E86	了	^？$(G)",
+        ];
+        let db: IdsDatabase = lines.iter().map(|i| IDS::from_str(i).unwrap()).collect();
+
+        let blob = db.to_binary();
+        assert_eq!(&blob[..4], binary::MAGIC);
+
+        let decoded = IdsDatabase::from_binary(&blob).unwrap();
+        assert_eq!(decoded.len(), db.len());
+        for (a, b) in db.iter().zip(decoded.iter()) {
+            assert_eq!(a, b);
+        }
+
+        let view = IdsBlob::new(&blob).unwrap();
+        assert_eq!(view.len(), 5);
+        assert_eq!(view.get('海').as_ref(), db.get('海'));
+        assert_eq!(view.get('傘').unwrap().xrefs, db.get('傘').unwrap().xrefs);
+        // Entries with a sequence are only available by index
+        assert!(view.get('漢').is_none());
+        assert_eq!(view.entry(3).unwrap().sequence.as_deref(), Some("漢︀"));
+        assert!(view.get('木').is_none());
+
+        let err = IdsBlob::new(b"IDSX").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidMagic);
+        let mut other_version = blob.clone();
+        other_version[4] = 9;
+        let err = IdsBlob::new(&other_version).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnsupportedVersion(9));
+        let err = IdsBlob::new(&blob[..30]).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnexpectedEnd);
+        let err = IdsDatabase::from_binary(&blob[..blob.len() - 3]).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnexpectedEnd);
+    }
+
+    #[test]
+    fn test_origin_set() {
+        let set = OriginSet::from_str("TGJ").unwrap();
+        assert_eq!(set.to_string(), "GJT");
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![Origin::China, Origin::Japan, Origin::Taiwan]
+        );
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(Origin::Japan) && !set.contains(Origin::SouthKorea));
+
+        let other = OriginSet::from([Origin::Japan, Origin::SouthKorea]);
+        assert_eq!((set | other).to_string(), "GJKT");
+        assert_eq!(set.intersection(other), OriginSet::from(Origin::Japan));
+        assert_eq!((set - other).to_string(), "GT");
+        assert!(OriginSet::from(Origin::Japan).is_subset(set));
+        assert!(set.is_disjoint(OriginSet::from(Origin::Vietnam)));
+        assert!(OriginSet::all().is_subset(OriginSet::from_bits(u16::MAX)));
+
+        let mut set = set;
+        assert!(!set.insert(Origin::China));
+        assert!(set.remove(Origin::China));
+        assert_eq!(set.to_string(), "JT");
+
+        let err = OriginSet::from_str("GJ?").unwrap_err();
+        assert_eq!(
+            (err.kind, err.column),
+            (ErrorKind::InvalidOrigin('?'), Some(2))
+        );
+
+        // Every origin with a letter survives Display, serde and composition strings
+        let lettered = OriginSet::all() - OriginSet::from(Origin::UCS2003);
+        let mut sets: Vec<OriginSet> = lettered.iter().map(OriginSet::from).collect();
+        sets.push(lettered);
+        for set in sets {
+            assert!(set.has_letters());
+            assert_eq!(OriginSet::from_str(&set.to_string()).unwrap(), set);
+            let json = serde_json::to_string(&set).unwrap();
+            assert_eq!(serde_json::from_str::<OriginSet>(&json).unwrap(), set);
+
+            let composition = Composition {
+                reg_origins: set,
+                bracketed_origins: set - OriginSet::from(Origin::China),
+                ..Composition::from_str("^⿱刃一$").unwrap()
+            };
+            let parsed = Composition::from_str(&composition.to_string()).unwrap();
+            assert_eq!(parsed, composition, "{composition}");
+        }
+        assert_eq!(lettered.to_string(), "GHJKMPSTBUVXZ");
+
+        // Origins without a letter are neither parsed nor written
+        let ucs = OriginSet::from([Origin::Unicode, Origin::UCS2003]);
+        assert!(!ucs.has_letters());
+        assert!(std::fmt::Write::write_fmt(&mut String::new(), format_args!("{ucs}")).is_err());
+        assert!(serde_json::to_string(&ucs).is_err());
+        let composition = Composition {
+            reg_origins: ucs,
+            ..Composition::from_str("^⿱刃一$").unwrap()
+        };
+        assert!(serde_json::to_string(&composition).is_err());
+        let err = OriginSet::from_str("GUCS20").unwrap_err();
+        assert_eq!(
+            (err.kind, err.column),
+            (ErrorKind::InvalidOrigin('C'), Some(2))
+        );
+
+        // Merging keeps origins bracketed only if they are bracketed in both
+        let mut a = Composition::from_str("^⿱刃一$(G[TJ])").unwrap();
+        let b = Composition::from_str("^⿱刃一$(T[JV])").unwrap();
+        a.merge_origins(&b);
+        assert_eq!(a.to_string(), "^⿱刃一$(G[J]T[V])");
+    }
+
+    #[test]
+    fn test_origin_codes() {
+        for origin in Origin::ALL {
+            assert_eq!(Origin::from_code(origin.code()), Some(origin));
+            assert_eq!(Origin::from_str(origin.code()).unwrap(), origin);
+            if let Some(letter) = origin.letter() {
+                assert_eq!(Origin::try_from(letter).unwrap(), origin);
+            }
+        }
+
+        assert_eq!(Origin::NordKorea.code(), "KP");
+        assert_eq!(Origin::NordKorea.to_string(), "P");
+        assert_eq!(Origin::from_str("uk").unwrap(), Origin::UK);
+        assert_eq!(Origin::from_str("B").unwrap(), Origin::UK);
+        assert_eq!(Origin::UCS2003.letter(), None);
+        assert!(Origin::from_str("KR").is_err());
+
+        let err = Composition::from_str("^⿱刃一$(GQ)").unwrap_err();
+        assert_eq!(
+            (err.kind, err.column),
+            (ErrorKind::InvalidOrigin('Q'), Some(7))
+        );
+        assert_eq!(err.field, Some(Field::Composition));
+        assert_eq!(err.input.as_deref(), Some("^⿱刃一$(GQ)"));
+
+        let err = IdsDatabase::from_reader(
+            "U+4E00\t一\t^一$(G)\nU+4E12\t丒\t^⿱刃一$(GQ)".as_bytes(),
+            LoadMode::Strict,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("line 2: composition field: invalid origin"));
+    }
 }
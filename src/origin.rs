@@ -1,9 +1,34 @@
+use std::{fmt::Display, str::FromStr};
+
 use serde::{Deserialize, Serialize};
 
-use crate::error::ParseError;
+use crate::error::{ErrorKind, ParseError};
 
-/// What origin a radical composition is from
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// What origin a radical composition is from.
+///
+/// BabelStone's IDS.TXT annotates compositions with single letters. The parser supports the
+/// letters below. Most of them refer to an IRG source, the remaining ones are annotations of
+/// BabelStone itself. Any other letter is rejected with `ErrorKind::InvalidOrigin`. IDS.TXT has
+/// no letter for `UCS2003`, so it can't be part of an origin list and is only parsed and written
+/// by its IRG source name, eg. in a `RegionPreference`.
+///
+/// | Variant            | Letter | IRG source |
+/// |--------------------|--------|------------|
+/// | `China`            | G      | G          |
+/// | `HongKong`         | H      | H          |
+/// | `Japan`            | J      | J          |
+/// | `SouthKorea`       | K      | K          |
+/// | `Macau`            | M      | M          |
+/// | `NordKorea`        | P      | KP         |
+/// | `Sat`              | S      | S          |
+/// | `Taiwan`           | T      | T          |
+/// | `UK`               | B      | UK         |
+/// | `Unicode`          | U      | U          |
+/// | `Vietnam`          | V      | V          |
+/// | `UCS2003`          | -      | UCS2003    |
+/// | `Alternative`      | X      | -          |
+/// | `UnifiableVariant` | Z      | -          |
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Origin {
     China,
     HongKong,
@@ -16,33 +41,128 @@ pub enum Origin {
     UK,
     Unicode,
     Vietnam,
+    /// The glyphs of the ISO/IEC 10646:2003 charts. IDS.TXT has no letter for it
     UCS2003,
+    /// An alternative composition which doesn't match a specific source glyph
     Alternative,
+    /// A composition of a unifiable variant of the source glyphs
     UnifiableVariant,
 }
 
+impl Origin {
+    /// All origins in their canonical order
+    pub const ALL: [Origin; 14] = [
+        Origin::China,
+        Origin::HongKong,
+        Origin::Japan,
+        Origin::SouthKorea,
+        Origin::Macau,
+        Origin::NordKorea,
+        Origin::Sat,
+        Origin::Taiwan,
+        Origin::UK,
+        Origin::Unicode,
+        Origin::Vietnam,
+        Origin::UCS2003,
+        Origin::Alternative,
+        Origin::UnifiableVariant,
+    ];
+
+    /// Returns the position of the origin in the canonical order
+    #[inline]
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Returns the letter the origin is written with in IDS.TXT. `None` for `UCS2003`
+    #[inline]
+    pub fn letter(&self) -> Option<char> {
+        Some(match self {
+            Origin::China => 'G',
+            Origin::HongKong => 'H',
+            Origin::Japan => 'J',
+            Origin::SouthKorea => 'K',
+            Origin::Macau => 'M',
+            Origin::NordKorea => 'P',
+            Origin::Sat => 'S',
+            Origin::Taiwan => 'T',
+            Origin::UK => 'B',
+            Origin::Unicode => 'U',
+            Origin::Vietnam => 'V',
+            Origin::UCS2003 => return None,
+            Origin::Alternative => 'X',
+            Origin::UnifiableVariant => 'Z',
+        })
+    }
+
+    /// Returns the name of the IRG source, eg. "KP" for `NordKorea`. `Alternative` and
+    /// `UnifiableVariant` aren't IRG sources and return their IDS.TXT letter instead.
+    #[inline]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Origin::China => "G",
+            Origin::HongKong => "H",
+            Origin::Japan => "J",
+            Origin::SouthKorea => "K",
+            Origin::Macau => "M",
+            Origin::NordKorea => "KP",
+            Origin::Sat => "S",
+            Origin::Taiwan => "T",
+            Origin::UK => "UK",
+            Origin::Unicode => "U",
+            Origin::Vietnam => "V",
+            Origin::UCS2003 => "UCS2003",
+            Origin::Alternative => "X",
+            Origin::UnifiableVariant => "Z",
+        }
+    }
+
+    /// Returns the origin of an IRG source name as returned by `code`
+    #[inline]
+    pub fn from_code(code: &str) -> Option<Origin> {
+        Origin::ALL
+            .into_iter()
+            .find(|i| i.code().eq_ignore_ascii_case(code))
+    }
+}
+
 // Parse an origin from its prefix code
 impl TryFrom<char> for Origin {
     type Error = ParseError;
 
-    /// Parse an origin from its prefix code
+    /// Parse an origin from its IDS.TXT letter
     #[inline]
     fn try_from(c: char) -> Result<Origin, Self::Error> {
-        Ok(match c.to_ascii_uppercase() {
-            'G' => Origin::China,
-            'H' => Origin::HongKong,
-            'J' => Origin::Japan,
-            'K' => Origin::SouthKorea,
-            'M' => Origin::Macau,
-            'P' => Origin::NordKorea,
-            'S' => Origin::Sat,
-            'T' => Origin::Taiwan,
-            'B' => Origin::UK,
-            'U' => Origin::Unicode,
-            'V' => Origin::Vietnam,
-            'X' => Origin::Alternative,
-            'Z' => Origin::UnifiableVariant,
-            _ => return Err(ParseError::InvalidOrigin(c)),
-        })
+        let upper = c.to_ascii_uppercase();
+        Origin::ALL
+            .into_iter()
+            .find(|i| i.letter() == Some(upper))
+            .ok_or_else(|| ErrorKind::InvalidOrigin(c).into())
+    }
+}
+
+/// Parses an origin from its IRG source name, eg. "KP", or its IDS.TXT letter, eg. "P"
+impl FromStr for Origin {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(origin) = Origin::from_code(s) {
+            return Ok(origin);
+        }
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Origin::try_from(c),
+            _ => Err(ErrorKind::InvalidOrigin(s.chars().next().unwrap_or_default()).into()),
+        }
+    }
+}
+
+/// Formats the origin as its IDS.TXT letter, or its IRG source name if it has no letter
+impl Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.letter() {
+            Some(letter) => write!(f, "{letter}"),
+            None => write!(f, "{}", self.code()),
+        }
     }
 }
@@ -0,0 +1,229 @@
+use std::{
+    fmt::{Debug, Display},
+    ops::{BitAnd, BitOr, Sub},
+    str::FromStr,
+};
+
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{error::ParseError, Origin};
+
+/// A set of `Origin`s stored as bitset. Iterates in the canonical order of `Origin::ALL`
+#[derive(Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct OriginSet(u16);
+
+impl OriginSet {
+    /// Creates a new and empty `OriginSet`
+    #[inline]
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Returns a set containing all origins
+    #[inline]
+    pub const fn all() -> Self {
+        Self((1 << Origin::ALL.len()) - 1)
+    }
+
+    /// Creates a set out of its raw bits. Bits without an origin get dropped
+    #[inline]
+    pub const fn from_bits(bits: u16) -> Self {
+        Self(bits & Self::all().0)
+    }
+
+    /// Returns the raw bits of the set, with bit `i` standing for `Origin::ALL[i]`
+    #[inline]
+    pub const fn bits(&self) -> u16 {
+        self.0
+    }
+
+    /// Adds `origin` to the set. Returns `true` if it wasn't contained yet
+    #[inline]
+    pub fn insert(&mut self, origin: Origin) -> bool {
+        let contained = self.contains(origin);
+        self.0 |= bit(origin);
+        !contained
+    }
+
+    /// Removes `origin` from the set. Returns `true` if it was contained
+    #[inline]
+    pub fn remove(&mut self, origin: Origin) -> bool {
+        let contained = self.contains(origin);
+        self.0 &= !bit(origin);
+        contained
+    }
+
+    #[inline]
+    pub fn contains(&self, origin: Origin) -> bool {
+        self.0 & bit(origin) != 0
+    }
+
+    /// Returns all origins contained in either set
+    #[inline]
+    pub fn union(&self, other: OriginSet) -> OriginSet {
+        Self(self.0 | other.0)
+    }
+
+    /// Returns all origins contained in both sets
+    #[inline]
+    pub fn intersection(&self, other: OriginSet) -> OriginSet {
+        Self(self.0 & other.0)
+    }
+
+    /// Returns all origins of `self` which are not contained in `other`
+    #[inline]
+    pub fn difference(&self, other: OriginSet) -> OriginSet {
+        Self(self.0 & !other.0)
+    }
+
+    /// Returns `true` if all origins of `self` are contained in `other`
+    #[inline]
+    pub fn is_subset(&self, other: OriginSet) -> bool {
+        self.difference(other).is_empty()
+    }
+
+    /// Returns `true` if both sets have no origin in common
+    #[inline]
+    pub fn is_disjoint(&self, other: OriginSet) -> bool {
+        self.intersection(other).is_empty()
+    }
+
+    /// Returns the amount of origins in the set
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Returns `true` if all origins have an IDS.TXT letter, so the set can be written. See
+    /// `Origin::letter`
+    #[inline]
+    pub fn has_letters(&self) -> bool {
+        self.iter().all(|i| i.letter().is_some())
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns an iterator over all origins in canonical order
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = Origin> {
+        let set = *self;
+        Origin::ALL.into_iter().filter(move |i| set.contains(*i))
+    }
+}
+
+#[inline]
+fn bit(origin: Origin) -> u16 {
+    1 << origin.index()
+}
+
+impl From<Origin> for OriginSet {
+    #[inline]
+    fn from(origin: Origin) -> Self {
+        Self(bit(origin))
+    }
+}
+
+impl FromIterator<Origin> for OriginSet {
+    #[inline]
+    fn from_iter<T: IntoIterator<Item = Origin>>(iter: T) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<Origin> for OriginSet {
+    #[inline]
+    fn extend<T: IntoIterator<Item = Origin>>(&mut self, iter: T) {
+        for origin in iter {
+            self.insert(origin);
+        }
+    }
+}
+
+impl<const N: usize> From<[Origin; N]> for OriginSet {
+    #[inline]
+    fn from(origins: [Origin; N]) -> Self {
+        origins.into_iter().collect()
+    }
+}
+
+impl BitOr for OriginSet {
+    type Output = OriginSet;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl BitAnd for OriginSet {
+    type Output = OriginSet;
+
+    #[inline]
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(rhs)
+    }
+}
+
+impl Sub for OriginSet {
+    type Output = OriginSet;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.difference(rhs)
+    }
+}
+
+/// Parses a set from its letter codes like "GHJKTV"
+impl FromStr for OriginSet {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut set = Self::new();
+        for (column, c) in s.chars().enumerate() {
+            set.insert(Origin::try_from(c).map_err(|e| e.at(column))?);
+        }
+        Ok(set)
+    }
+}
+
+/// Formats the set as its letter codes like "GHJKTV". Fails if the set contains an origin
+/// without a letter, see `OriginSet::has_letters`.
+impl Display for OriginSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for origin in self.iter() {
+            write!(f, "{}", origin.letter().ok_or(std::fmt::Error)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for OriginSet {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// Serializes the set as its letter codes. Fails if the set contains an origin without a letter
+impl Serialize for OriginSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !self.has_letters() {
+            let err = "origins without an IDS.TXT letter can't be written";
+            return Err(serde::ser::Error::custom(err));
+        }
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for OriginSet {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
@@ -0,0 +1,208 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::Serialize;
+
+use crate::{error::ParseError, Composition, Origin, IDS};
+
+/// An ordered list of origins used to select one of the compositions of an entry
+#[derive(Copy, Clone, Debug)]
+pub struct RegionPreference {
+    order: [Origin; Origin::ALL.len()],
+    len: usize,
+    fallback: bool,
+}
+
+/// A composition selected by a `RegionPreference`
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub struct Selection<'a> {
+    pub composition: &'a Composition,
+    pub reason: SelectionReason,
+}
+
+/// Why a composition got selected
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionReason {
+    /// The composition has the origin at position `rank` of the preference list
+    Preferred { origin: Origin, rank: usize },
+    /// No composition has a preferred origin, so the first one got selected
+    Fallback,
+}
+
+impl RegionPreference {
+    /// Creates a new preference in the order of `origins`. Origins listed more than once are
+    /// only taken into account at their first position. Falls back to the first composition if
+    /// none of the origins match.
+    pub fn new<I: IntoIterator<Item = Origin>>(origins: I) -> Self {
+        let mut preference = Self::default();
+        for origin in origins {
+            preference.push(origin);
+        }
+        preference
+    }
+
+    /// Returns the preference commonly used for a BCP 47 locale tag like "ja", "zh-Hant" or
+    /// "ko-KR", or `None` if the language isn't written with Han characters.
+    pub fn from_locale(tag: &str) -> Option<Self> {
+        use Origin::*;
+
+        let tag = tag.to_ascii_lowercase();
+        let mut subtags = tag.split(['-', '_']);
+        let language = subtags.next()?;
+        let subtags: Vec<&str> = subtags.collect();
+        let has = |subtag: &str| subtags.contains(&subtag);
+
+        let origins: &[Origin] = match language {
+            "ja" => &[Japan, Taiwan, HongKong],
+            "ko" if has("kp") => &[NordKorea, SouthKorea, Taiwan],
+            "ko" => &[SouthKorea, Taiwan],
+            "vi" => &[Vietnam, Taiwan],
+            "zh" | "yue" if has("hk") => &[HongKong, Taiwan, Macau],
+            "zh" | "yue" if has("mo") => &[Macau, HongKong, Taiwan],
+            "zh" if has("tw") || has("hant") => &[Taiwan, HongKong, Macau],
+            "yue" => &[HongKong, Taiwan, Macau],
+            "zh" => &[China],
+            _ => return None,
+        };
+        Some(Self::new(origins.iter().copied()))
+    }
+
+    /// Don't select any composition if none of the preferred origins match
+    #[inline]
+    pub fn without_fallback(mut self) -> Self {
+        self.fallback = false;
+        self
+    }
+
+    /// Appends `origin` to the end of the list if it isn't already part of it
+    pub fn push(&mut self, origin: Origin) {
+        if !self.origins().contains(&origin) {
+            self.order[self.len] = origin;
+            self.len += 1;
+        }
+    }
+
+    /// Returns the preferred origins, most preferred first
+    #[inline]
+    pub fn origins(&self) -> &[Origin] {
+        &self.order[..self.len]
+    }
+
+    /// Returns `true` if the first composition gets selected when no preferred origin matches
+    #[inline]
+    pub fn has_fallback(&self) -> bool {
+        self.fallback
+    }
+
+    /// Selects the composition of `ids` with the most preferred origin
+    pub fn select<'a>(&self, ids: &'a IDS) -> Option<Selection<'a>> {
+        for (rank, origin) in self.origins().iter().enumerate() {
+            if let Some(composition) = ids.comp_by_lang(*origin) {
+                let reason = SelectionReason::Preferred {
+                    origin: *origin,
+                    rank,
+                };
+                return Some(Selection {
+                    composition,
+                    reason,
+                });
+            }
+        }
+
+        if !self.fallback {
+            return None;
+        }
+        ids.compositions.first().map(|composition| Selection {
+            composition,
+            reason: SelectionReason::Fallback,
+        })
+    }
+}
+
+impl Default for RegionPreference {
+    /// Creates an empty preference, always selecting the first composition
+    #[inline]
+    fn default() -> Self {
+        Self {
+            order: Origin::ALL,
+            len: 0,
+            fallback: true,
+        }
+    }
+}
+
+impl PartialEq for RegionPreference {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.origins() == other.origins() && self.fallback == other.fallback
+    }
+}
+
+impl Eq for RegionPreference {}
+
+impl From<Origin> for RegionPreference {
+    #[inline]
+    fn from(origin: Origin) -> Self {
+        Self::new([origin])
+    }
+}
+
+impl FromIterator<Origin> for RegionPreference {
+    #[inline]
+    fn from_iter<T: IntoIterator<Item = Origin>>(iter: T) -> Self {
+        Self::new(iter)
+    }
+}
+
+/// Parses a comma separated list of origins like "J,T,H" or "KP,K". See `Origin::from_str`
+impl FromStr for RegionPreference {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut column = 0;
+        let mut preference = Self::default();
+        if s.trim().is_empty() {
+            return Ok(preference);
+        }
+        for code in s.split(',') {
+            // Point at the code itself rather than the whitespace around it
+            let leading = code.chars().count() - code.trim_start().chars().count();
+            let origin = Origin::from_str(code.trim()).map_err(|e| e.at(column + leading))?;
+            preference.push(origin);
+            column += code.chars().count() + 1;
+        }
+        Ok(preference)
+    }
+}
+
+/// Formats the preference as comma separated list of IRG source names
+impl Display for RegionPreference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, origin) in self.origins().iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", origin.code())?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for SelectionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SelectionReason::Preferred { origin, rank } => {
+                write!(f, "preferred origin {} (#{})", origin.code(), rank + 1)
+            }
+            SelectionReason::Fallback => write!(f, "no preferred origin, first composition"),
+        }
+    }
+}
+
+impl IDS {
+    /// Returns the composition of the most preferred origin. See `RegionPreference::select`
+    #[inline]
+    pub fn select(&self, preference: &RegionPreference) -> Option<Selection<'_>> {
+        preference.select(self)
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::Read,
+    path::Path,
+    str::FromStr,
+    sync::OnceLock,
+};
+
+use crate::{
+    error::{ErrorKind, LoadError, ParseError},
+    sepecial_components::conv_special,
+    unencoded::UnencodedTable,
+    utils,
+};
+
+/// Amount of unencoded components mapped by the built in layout
+const BUILTIN_COUNT: u32 = 121;
+
+/// Maps the numbers of unencoded components (`{NN}`) to the Private Use Area characters a font
+/// displays them with
+#[derive(Default, Clone, Debug)]
+pub struct PuaMap {
+    chars: BTreeMap<u32, char>,
+    numbers: HashMap<char, u32>,
+    /// Character returned for numbers without a mapping
+    fallback: Option<char>,
+}
+
+impl PuaMap {
+    /// Displayed for unknown components by `PuaMap::display` if no fallback is set
+    pub const REPLACEMENT: char = '〓';
+
+    /// Creates a new and empty `PuaMap`
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the built in layout, which has no fallback
+    pub fn builtin() -> &'static PuaMap {
+        static BUILTIN: OnceLock<PuaMap> = OnceLock::new();
+        BUILTIN.get_or_init(|| {
+            let mut map = Self::new();
+            for nr in 1..=BUILTIN_COUNT {
+                if let Some(c) = conv_special(nr) {
+                    map.insert(nr, c);
+                }
+            }
+            map
+        })
+    }
+
+    /// Loads the layout at `path`
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        Self::from_reader(File::open(path)?)
+    }
+
+    /// Loads a layout from `reader`. See the `FromStr` implementation for the format.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, LoadError> {
+        Ok(utils::read_table(reader, parse_line)?.into_iter().collect())
+    }
+
+    /// Sets the character returned for numbers without a mapping
+    #[inline]
+    pub fn with_fallback(mut self, fallback: char) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    /// Maps the component `number` to `c`, replacing the previous mappings of both `number` and
+    /// `c`
+    pub fn insert(&mut self, number: u32, c: char) {
+        if let Some(prev) = self.chars.insert(number, c) {
+            self.numbers.remove(&prev);
+        }
+        if let Some(prev) = self.numbers.insert(c, number) {
+            if prev != number {
+                self.chars.remove(&prev);
+            }
+        }
+    }
+
+    /// Returns the character of the component `number`, or the fallback if there is none.
+    /// Fails if neither exists.
+    #[inline]
+    pub fn get(&self, number: u32) -> Result<char, ParseError> {
+        self.lookup(number)
+            .or(self.fallback)
+            .ok_or_else(|| ErrorKind::UnknownUnencodedComponent(number).into())
+    }
+
+    /// Returns the character of the component `number` without falling back
+    #[inline]
+    pub fn lookup(&self, number: u32) -> Option<char> {
+        self.chars.get(&number).copied()
+    }
+
+    /// Returns a displayable character for the component `number`, which is
+    /// `PuaMap::REPLACEMENT` if there is neither a mapping nor a fallback
+    #[inline]
+    pub fn display(&self, number: u32) -> char {
+        self.get(number).unwrap_or(Self::REPLACEMENT)
+    }
+
+    /// Returns the number of the component displayed with `c`
+    #[inline]
+    pub fn number_of(&self, c: char) -> Option<u32> {
+        self.numbers.get(&c).copied()
+    }
+
+    /// Returns an iterator over all mappings ordered by number
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (u32, char)> + '_ {
+        self.chars.iter().map(|(nr, c)| (*nr, *c))
+    }
+
+    /// Returns the amount of mapped components
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    /// Returns `true` if no component is mapped
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+}
+
+/// Takes the code points of all components of the side table which have one
+impl From<&UnencodedTable> for PuaMap {
+    fn from(table: &UnencodedTable) -> Self {
+        let mut map = Self::new();
+        for component in table.iter() {
+            if let Some(c) = component.code_point {
+                map.insert(component.number, c);
+            }
+        }
+        map
+    }
+}
+
+impl FromIterator<(u32, char)> for PuaMap {
+    fn from_iter<T: IntoIterator<Item = (u32, char)>>(iter: T) -> Self {
+        let mut map = Self::new();
+        for (number, c) in iter {
+            map.insert(number, c);
+        }
+        map
+    }
+}
+
+/// Parses a layout with one component per line of the format "{NN} U+XXXX", separated by
+/// whitespace. Empty lines and lines starting with '#' get skipped.
+impl FromStr for PuaMap {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(utils::parse_table_str(s, parse_line)?.into_iter().collect())
+    }
+}
+
+/// Parses a single line of a layout
+fn parse_line(line: &str) -> Result<(u32, char), ParseError> {
+    let trimmed = line.trim_start();
+
+    let start = line.chars().count() - trimmed.chars().count();
+    let (number, code) = trimmed
+        .split_once(char::is_whitespace)
+        .unwrap_or((trimmed, ""));
+    let code_start = start + number.chars().count() + 1;
+    let code_column = code_start + (code.chars().count() - code.trim_start().chars().count());
+    let code = code.trim();
+
+    let number = number
+        .strip_prefix('{')
+        .and_then(|i| i.strip_suffix('}'))
+        .and_then(|i| i.parse().ok())
+        .ok_or_else(|| ParseError::new(ErrorKind::UnexpectedCharacter).at(start))?;
+
+    let c = utils::utf_code_to_char(code)
+        .filter(|_| !code.contains(char::is_whitespace))
+        .ok_or_else(|| ParseError::new(ErrorKind::InvalidCodePoint).at(code_column))?;
+
+    Ok((number, c))
+}
@@ -0,0 +1,275 @@
+use std::str::FromStr;
+
+use crate::{
+    composition::{CompositionPart, Modifier},
+    database::IdsDatabase,
+    destr_form::DestructionForm,
+    error::{ErrorKind, ParseError},
+    origin_set::OriginSet,
+    radical_map::{Placement, RadicalMap},
+    tree::IdsNode,
+    Composition, Origin,
+};
+
+/// A pattern matched against the tree of a composition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// `*`: Matches any sub tree
+    Any,
+    /// `?`: Matches exactly one component
+    AnyComponent,
+    /// Matches the given radical or unencoded component
+    Component(CompositionPart),
+    Operator(DestructionForm, Vec<Pattern>),
+    Modified(Modifier, Box<Pattern>),
+    /// `{a|b}`: Matches if any of the patterns matches
+    Alternation(Vec<Pattern>),
+}
+
+/// A query for characters by their composition like "⿰氵?", "⿱艹*" or "⿰?{口|日}$(J)"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdsQuery {
+    pub pattern: Pattern,
+    /// Only match compositions of those origins. Matches all compositions if empty
+    pub origins: OriginSet,
+}
+
+/// A single character matching an `IdsQuery`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryMatch {
+    pub literal: char,
+    /// Amount of nodes covered by wildcards. The lower, the more specific the match
+    pub cost: usize,
+}
+
+impl Pattern {
+    /// Matches the pattern against `node`. Returns the amount of nodes covered by wildcards
+    pub fn matches(&self, node: &IdsNode) -> Option<usize> {
+        match (self, node) {
+            (Pattern::Any, node) => Some(node.iter().count()),
+            (Pattern::AnyComponent, IdsNode::Leaf(_)) => Some(1),
+            (Pattern::Component(part), IdsNode::Leaf(leaf)) => {
+                // Compositions keep their radicals as written, patterns are folded while parsing
+                let folded = leaf.folded(RadicalMap::standard(), Placement::Any);
+                (part == leaf || *part == folded).then_some(0)
+            }
+            (Pattern::Operator(form, patterns), IdsNode::Operator(node_form, children)) => {
+                if form != node_form || patterns.len() != children.len() {
+                    return None;
+                }
+                patterns
+                    .iter()
+                    .zip(children)
+                    .map(|(pattern, child)| pattern.matches(child))
+                    .sum()
+            }
+            (Pattern::Modified(modifier, pattern), IdsNode::Modified(node_modifier, child)) => {
+                if modifier != node_modifier {
+                    return None;
+                }
+                pattern.matches(child)
+            }
+            (Pattern::Alternation(patterns), node) => {
+                patterns.iter().filter_map(|i| i.matches(node)).min()
+            }
+            _ => None,
+        }
+    }
+}
+
+impl IdsQuery {
+    /// Matches the query against `composition`. Returns the amount of nodes covered by wildcards
+    #[inline]
+    pub fn matches(&self, composition: &Composition) -> Option<usize> {
+        let origin_matches =
+            self.origins.is_empty() || !self.origins.is_disjoint(composition.reg_origins);
+        if !origin_matches {
+            return None;
+        }
+        self.pattern.matches(&composition.tree)
+    }
+}
+
+impl IdsDatabase {
+    /// Returns all characters with a composition matching `query`, the most specific first
+    pub fn search(&self, query: &IdsQuery) -> Vec<QueryMatch> {
+        let mut matches: Vec<QueryMatch> = self
+            .iter()
+            .filter_map(|ids| {
+                let cost = ids
+                    .compositions
+                    .iter()
+                    .filter_map(|i| query.matches(i))
+                    .min()?;
+                Some(QueryMatch {
+                    literal: ids.literal,
+                    cost,
+                })
+            })
+            .collect();
+        matches.sort_by_key(|i| (i.cost, i.literal));
+        matches
+    }
+}
+
+/// Parses a query of the format "⿰?{口|日}$(J)"
+impl FromStr for IdsQuery {
+    type Err = ParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        IdsQuery::parse_with(s, RadicalMap::standard())
+    }
+}
+
+impl IdsQuery {
+    /// Parses a query, folding its radicals with `map`. Use `RadicalMap::with_radical_blocks` to
+    /// also match Kangxi radicals and CJK Radicals Supplement forms.
+    pub fn parse_with(s: &str, map: &RadicalMap) -> Result<Self, ParseError> {
+        let chars: Vec<char> = s.chars().collect();
+        let end = chars.iter().position(|i| *i == '$').unwrap_or(chars.len());
+
+        let mut parser = PatternParser {
+            chars: &chars[..end],
+            pos: 0,
+            map,
+        };
+        let pattern = parser.parse_all()?;
+
+        let mut origins = OriginSet::new();
+        if end == chars.len() {
+            return Ok(IdsQuery { pattern, origins });
+        }
+
+        // The origin suffix has to be of the exact format "$(…)"
+        let unexpected = |column: usize| ParseError::new(ErrorKind::UnexpectedCharacter).at(column);
+        if chars.get(end + 1) != Some(&'(') {
+            return Err(unexpected(end + 1));
+        }
+        let close = chars[end + 2..]
+            .iter()
+            .position(|i| *i == ')')
+            .map(|i| end + 2 + i)
+            .ok_or_else(|| unexpected(chars.len()))?;
+        if close + 1 != chars.len() {
+            return Err(unexpected(close + 1));
+        }
+
+        for (column, c) in chars.iter().enumerate().take(close).skip(end + 2) {
+            origins.insert(Origin::try_from(*c).map_err(|e| e.at(column))?);
+        }
+
+        Ok(IdsQuery { pattern, origins })
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = ParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars: Vec<char> = s.chars().collect();
+        PatternParser {
+            chars: &chars,
+            pos: 0,
+            map: RadicalMap::standard(),
+        }
+        .parse_all()
+    }
+}
+
+struct PatternParser<'a> {
+    chars: &'a [char],
+    pos: usize,
+    map: &'a RadicalMap,
+}
+
+impl PatternParser<'_> {
+    /// Parses a single pattern which has to span all chars
+    fn parse_all(&mut self) -> Result<Pattern, ParseError> {
+        let pattern = self.parse(ErrorKind::EmptyComposition)?;
+        if self.pos != self.chars.len() {
+            let err = ErrorKind::TrailingComponents(self.chars.len() - self.pos);
+            return Err(ParseError::new(err).at(self.pos));
+        }
+        Ok(pattern)
+    }
+
+    /// Parses the pattern at the current position. Returns `missing` if there is no char left.
+    fn parse(&mut self, missing: ErrorKind) -> Result<Pattern, ParseError> {
+        let c = *self
+            .chars
+            .get(self.pos)
+            .ok_or_else(|| ParseError::new(missing).at(self.pos))?;
+        let start = self.pos;
+        self.pos += 1;
+
+        Ok(match c {
+            '*' => Pattern::Any,
+            '?' => Pattern::AnyComponent,
+            '{' => self.parse_braces(start)?,
+            c => match CompositionPart::parse(c, self.map).map_err(|e| e.at(start))? {
+                CompositionPart::Destructive(form) => {
+                    let patterns = (0..form.arity())
+                        .map(|_| self.parse(ErrorKind::MissingOperand(form)))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Pattern::Operator(form, patterns)
+                }
+                CompositionPart::Modifier(modifier) if modifier.arity() == 1 => {
+                    let pattern = self.parse(ErrorKind::DanglingModifier(modifier))?;
+                    Pattern::Modified(modifier, Box::new(pattern))
+                }
+                part => Pattern::Component(part),
+            },
+        })
+    }
+
+    /// Parses either an unencoded component like `{02}` or an alternation like `{口|日}`
+    fn parse_braces(&mut self, start: usize) -> Result<Pattern, ParseError> {
+        let unexpected = || ParseError::new(ErrorKind::UnexpectedCharacter).at(start);
+
+        // Find the matching closing brace and all top level separators
+        let mut depth = 0;
+        let mut separators = vec![];
+        let mut end = None;
+        for (pos, c) in self.chars.iter().enumerate().skip(self.pos) {
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => {
+                    end = Some(pos);
+                    break;
+                }
+                '}' => depth -= 1,
+                '|' if depth == 0 => separators.push(pos),
+                _ => (),
+            }
+        }
+        let end = end.ok_or_else(unexpected)?;
+        let inner = &self.chars[self.pos..end];
+
+        if !inner.is_empty() && inner.iter().all(|i| i.is_ascii_digit()) {
+            let nr: u32 = inner
+                .iter()
+                .collect::<String>()
+                .parse()
+                .map_err(|_| unexpected())?;
+            self.pos = end + 1;
+            return Ok(Pattern::Component(CompositionPart::UnencodedComponent(nr)));
+        }
+
+        let mut alternatives = vec![];
+        let mut alt_start = self.pos;
+        for alt_end in separators.into_iter().chain(std::iter::once(end)) {
+            let mut parser = PatternParser {
+                chars: &self.chars[..alt_end],
+                pos: alt_start,
+                map: self.map,
+            };
+            alternatives.push(parser.parse_all()?);
+            alt_start = alt_end + 1;
+        }
+
+        self.pos = end + 1;
+        Ok(Pattern::Alternation(alternatives))
+    }
+}
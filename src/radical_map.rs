@@ -0,0 +1,334 @@
+use std::{collections::HashMap, fmt::Display, io::Read, str::FromStr, sync::OnceLock};
+
+use crate::{
+    destr_form::DestructionForm,
+    error::{ErrorKind, LoadError, ParseError},
+    kangxi, utils,
+};
+
+/// Variant forms of radicals as (base, variant, placement). The base is the form used in the ids
+/// dataset, the variants are other unified ideographs of the same radical. Kangxi radicals and
+/// CJK Radicals Supplement forms are left to `RadicalMap::with_radical_blocks`, CJK Compatibility
+/// Ideographs are added from their canonical decompositions, see `COMPATIBILITY`.
+const VARIANTS: &[(char, char, Placement)] = &[
+    ('牛', '牜', Placement::Any),
+    ('玉', '𤣩', Placement::Any),
+    ('竹', '𥫗', Placement::Any),
+    ('艸', '艹', Placement::Any),
+    ('糸', '糹', Placement::Any),
+    ('言', '訁', Placement::Any),
+    ('金', '釒', Placement::Any),
+    ('食', '飠', Placement::Any),
+    ('肉', '月', Placement::Left),
+    ('孑', '子', Placement::Any),
+    ('⺶', '羊', Placement::Any),
+];
+
+/// The CJK Compatibility Ideographs and CJK Compatibility Ideographs Supplement blocks
+const COMPATIBILITY: [std::ops::RangeInclusive<char>; 2] =
+    ['\u{F900}'..='\u{FAFF}', '\u{2F800}'..='\u{2FA1F}'];
+
+/// Position of a component within its parent `DestructionForm`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Placement {
+    /// Any position, including components without a parent
+    #[default]
+    Any,
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// A single variant form of a radical
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RadicalVariant {
+    /// The form the variant gets folded to
+    pub base: char,
+    pub variant: char,
+    /// Only fold the variant at this position
+    pub placement: Placement,
+}
+
+/// Bidirectional mapping between radicals and their variant forms
+#[derive(Default, Clone, Debug)]
+pub struct RadicalMap {
+    variants: Vec<RadicalVariant>,
+    by_variant: HashMap<char, Vec<usize>>,
+    by_base: HashMap<char, Vec<usize>>,
+}
+
+impl Placement {
+    /// Returns the placement of the `index`th child of `form`. ⿰ and ⿲ are split by vertical
+    /// lines, thus place their children left and right.
+    #[inline]
+    pub fn of(form: DestructionForm, index: usize) -> Self {
+        match (form, index) {
+            (DestructionForm::Vertically | DestructionForm::Vertically3, 0) => Placement::Left,
+            (DestructionForm::Vertically, 1) | (DestructionForm::Vertically3, 2) => {
+                Placement::Right
+            }
+            (DestructionForm::Horizontally | DestructionForm::Horizontally3, 0) => Placement::Top,
+            (DestructionForm::Horizontally, 1) | (DestructionForm::Horizontally3, 2) => {
+                Placement::Bottom
+            }
+            _ => Placement::Any,
+        }
+    }
+}
+
+impl RadicalMap {
+    /// Creates a new and empty `RadicalMap`, which doesn't fold anything
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the built in mapping used by default while normalizing and searching. Besides the
+    /// radical variants it folds every CJK Compatibility Ideograph to its canonical
+    /// decomposition, eg. U+F90A to 金.
+    pub fn standard() -> &'static RadicalMap {
+        static STANDARD: OnceLock<RadicalMap> = OnceLock::new();
+        STANDARD.get_or_init(|| {
+            let mut map: RadicalMap = VARIANTS
+                .iter()
+                .map(|(base, variant, placement)| RadicalVariant {
+                    base: *base,
+                    variant: *variant,
+                    placement: *placement,
+                })
+                .collect();
+
+            for c in COMPATIBILITY.into_iter().flatten() {
+                let Some(unified) = canonical_decomposition(c) else {
+                    continue;
+                };
+                map.insert(RadicalVariant {
+                    base: map.fold(unified, Placement::Any),
+                    variant: c,
+                    placement: Placement::Any,
+                });
+            }
+            map
+        })
+    }
+
+    /// Returns the built in mapping extended by all Kangxi radicals and CJK Radicals Supplement
+    /// forms. Those get folded to `kangxi::to_unified` and from there on like the standard
+    /// mapping does, eg. ⺮ to 𥫗 to 竹.
+    pub fn with_radical_blocks() -> &'static RadicalMap {
+        static EXTENDED: OnceLock<RadicalMap> = OnceLock::new();
+        EXTENDED.get_or_init(|| {
+            let standard = Self::standard();
+            let mut map = standard.clone();
+            for (radical, unified) in kangxi::pairs() {
+                map.insert(RadicalVariant {
+                    base: standard.fold(unified, Placement::Any),
+                    variant: radical,
+                    placement: Placement::Any,
+                });
+            }
+            map
+        })
+    }
+
+    /// Loads a mapping from `reader`. See the `FromStr` implementation for the format.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, LoadError> {
+        Ok(utils::read_table(reader, parse_line)?.into_iter().collect())
+    }
+
+    /// Adds a variant to the mapping
+    pub fn insert(&mut self, variant: RadicalVariant) {
+        if self.variants.contains(&variant) {
+            return;
+        }
+        let pos = self.variants.len();
+        self.by_variant
+            .entry(variant.variant)
+            .or_default()
+            .push(pos);
+        self.by_base.entry(variant.base).or_default().push(pos);
+        self.variants.push(variant);
+    }
+
+    /// Folds `c` into its base form if it is a variant placed at `placement`. Variants bound to a
+    /// specific placement take precedence over those valid anywhere.
+    pub fn fold(&self, c: char, placement: Placement) -> char {
+        let Some(positions) = self.by_variant.get(&c) else {
+            return c;
+        };
+
+        let mut fallback = None;
+        for variant in positions.iter().map(|i| &self.variants[*i]) {
+            if variant.placement == placement {
+                return variant.base;
+            }
+            if variant.placement == Placement::Any && fallback.is_none() {
+                fallback = Some(variant.base);
+            }
+        }
+        fallback.unwrap_or(c)
+    }
+
+    /// Returns all variant forms of `base`
+    #[inline]
+    pub fn variants(&self, base: char) -> Vec<char> {
+        let mut out: Vec<char> = self
+            .by_base
+            .get(&base)
+            .into_iter()
+            .flatten()
+            .map(|i| self.variants[*i].variant)
+            .collect();
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+
+    /// Returns all bases `c` is a variant of, regardless of the placement
+    #[inline]
+    pub fn bases(&self, c: char) -> Vec<char> {
+        let mut out: Vec<char> = self
+            .by_variant
+            .get(&c)
+            .into_iter()
+            .flatten()
+            .map(|i| self.variants[*i].base)
+            .collect();
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+
+    /// Returns an iterator over all variants in the order they were added
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &RadicalVariant> {
+        self.variants.iter()
+    }
+
+    /// Returns `true` if the mapping contains no variants
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+}
+
+impl FromIterator<RadicalVariant> for RadicalMap {
+    fn from_iter<T: IntoIterator<Item = RadicalVariant>>(iter: T) -> Self {
+        let mut map = Self::new();
+        for variant in iter {
+            map.insert(variant);
+        }
+        map
+    }
+}
+
+/// Parses a mapping with one variant per line of the format "肉 月 left". The placement is
+/// optional, empty lines and lines starting with '#' get skipped.
+impl FromStr for RadicalMap {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(utils::parse_table_str(s, parse_line)?.into_iter().collect())
+    }
+}
+
+impl FromStr for Placement {
+    type Err = ParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "any" => Placement::Any,
+            "left" => Placement::Left,
+            "right" => Placement::Right,
+            "top" => Placement::Top,
+            "bottom" => Placement::Bottom,
+            _ => return Err(ParseError::new(ErrorKind::UnexpectedCharacter).at(0)),
+        })
+    }
+}
+
+impl Display for Placement {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Placement::Any => "any",
+            Placement::Left => "left",
+            Placement::Right => "right",
+            Placement::Top => "top",
+            Placement::Bottom => "bottom",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Formats the mapping in the format parsed by its `FromStr` implementation
+impl Display for RadicalMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for variant in &self.variants {
+            writeln!(
+                f,
+                "{} {} {}",
+                variant.base, variant.variant, variant.placement
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the unified ideograph a compatibility ideograph canonically decomposes to, or `None`
+/// if `c` doesn't decompose to a single other character
+fn canonical_decomposition(c: char) -> Option<char> {
+    let mut decomposed = vec![];
+    unicode_normalization::char::decompose_canonical(c, |i| decomposed.push(i));
+    match decomposed.as_slice() {
+        [unified] if *unified != c => Some(*unified),
+        _ => None,
+    }
+}
+
+/// Parses a single line of a mapping
+fn parse_line(line: &str) -> Result<RadicalVariant, ParseError> {
+    // All whitespace separated fields together with their column
+    let mut fields: Vec<(usize, String)> = vec![];
+    let mut prev_space = true;
+    for (column, c) in line.chars().enumerate() {
+        if !c.is_whitespace() {
+            if prev_space {
+                fields.push((column, String::new()));
+            }
+            fields.last_mut().unwrap().1.push(c);
+        }
+        prev_space = c.is_whitespace();
+    }
+
+    let err = |column: usize| ParseError::new(ErrorKind::UnexpectedCharacter).at(column);
+    let single_char = |(column, field): &(usize, String)| {
+        let mut chars = field.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(err(*column)),
+        }
+    };
+
+    let (base, variant) = match fields.as_slice() {
+        [base, variant, ..] => (single_char(base)?, single_char(variant)?),
+        _ => return Err(err(line.chars().count())),
+    };
+
+    let placement = match fields.get(2..).unwrap_or_default() {
+        [] => Placement::Any,
+        [(column, placement)] => Placement::from_str(placement).map_err(|e| e.shifted(*column))?,
+        [_, (column, _), ..] => {
+            let err = ErrorKind::TrailingComponents(fields.len() - 3);
+            return Err(ParseError::new(err).at(*column));
+        }
+    };
+
+    Ok(RadicalVariant {
+        base,
+        variant,
+        placement,
+    })
+}
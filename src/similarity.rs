@@ -0,0 +1,119 @@
+use crate::{database::IdsDatabase, tree::IdsNode, IDS};
+
+/// Costs of the single edit operations used by `tree_distance`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EditCosts {
+    /// Replacing a component with another one
+    pub replace_component: u32,
+    /// Changing a `DestructionForm` or `Modifier` into another one
+    pub change_form: u32,
+    /// Inserting or deleting a single node. Sub trees cost this for each of their nodes
+    pub insert_node: u32,
+}
+
+impl Default for EditCosts {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            replace_component: 2,
+            change_form: 1,
+            insert_node: 1,
+        }
+    }
+}
+
+/// A character similar to another one
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Similar {
+    pub literal: char,
+    pub distance: u32,
+}
+
+/// Returns the edit distance between two trees. Nodes get only matched if their parents are
+/// matched as well, and children are aligned in order. Both trees should be normalized.
+pub fn tree_distance(a: &IdsNode, b: &IdsNode, costs: &EditCosts) -> u32 {
+    let relabel = relabel_cost(a, b, costs);
+
+    let (a_children, b_children) = (a.children(), b.children());
+    let insert = |node: &IdsNode| node.iter().count() as u32 * costs.insert_node;
+
+    // Edit distance between both lists of children
+    let mut prev: Vec<u32> = std::iter::once(0)
+        .chain(b_children.iter().scan(0, |acc, i| {
+            *acc += insert(i);
+            Some(*acc)
+        }))
+        .collect();
+
+    for a_child in a_children {
+        let mut row = vec![prev[0] + insert(a_child)];
+        for (j, b_child) in b_children.iter().enumerate() {
+            let cost = (prev[j + 1] + insert(a_child))
+                .min(row[j] + insert(b_child))
+                .min(prev[j] + tree_distance(a_child, b_child, costs));
+            row.push(cost);
+        }
+        prev = row;
+    }
+
+    relabel + prev[b_children.len()]
+}
+
+/// Returns the smallest edit distance between any compositions of `a` and `b`
+#[inline]
+pub fn ids_distance(a: &IDS, b: &IDS, costs: &EditCosts) -> Option<u32> {
+    min_distance(&normalized_trees(a), &normalized_trees(b), costs)
+}
+
+/// Returns the smallest edit distance between any of the trees of `a` and `b`
+fn min_distance(a: &[IdsNode], b: &[IdsNode], costs: &EditCosts) -> Option<u32> {
+    a.iter()
+        .flat_map(|a| b.iter().map(move |b| tree_distance(a, b, costs)))
+        .min()
+}
+
+#[inline]
+fn normalized_trees(ids: &IDS) -> Vec<IdsNode> {
+    ids.compositions.iter().map(|i| i.key()).collect()
+}
+
+impl IdsDatabase {
+    /// Returns the `k` characters which are structurally the most similar to `literal`, the
+    /// most similar first. The normalized trees of all entries get computed once and are reused
+    /// by later calls.
+    pub fn similar(&self, literal: char, k: usize, costs: &EditCosts) -> Vec<Similar> {
+        let Some(trees) = self.normalized_trees_of(literal) else {
+            return vec![];
+        };
+
+        let mut similar: Vec<Similar> = self
+            .iter()
+            .zip(self.normalized_trees())
+            .filter(|(i, _)| i.literal != literal)
+            .filter_map(|(other, other_trees)| {
+                let distance = min_distance(trees, other_trees, costs)?;
+                Some(Similar {
+                    literal: other.literal,
+                    distance,
+                })
+            })
+            .collect();
+
+        similar.sort_by_key(|i| (i.distance, i.literal));
+        similar.truncate(k);
+        similar
+    }
+}
+
+fn relabel_cost(a: &IdsNode, b: &IdsNode, costs: &EditCosts) -> u32 {
+    if a.part() == b.part() {
+        return 0;
+    }
+
+    match (a, b) {
+        (IdsNode::Leaf(_), IdsNode::Leaf(_)) => costs.replace_component,
+        (IdsNode::Operator(..), IdsNode::Operator(..))
+        | (IdsNode::Modified(..), IdsNode::Modified(..)) => costs.change_form,
+        _ => costs.replace_component + costs.change_form,
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    hash::Hash,
+};
+
+use serde::Serialize;
+
+use crate::{
+    component_index::{char_key, collect_components, component_key},
+    composition::CompositionPart,
+    database::IdsDatabase,
+    decompose::DecomposeOptions,
+    destr_form::DestructionForm,
+    origin_set::OriginSet,
+    tree::IdsNode,
+    Origin, IDS,
+};
+
+/// Statistics over all entries of an `IdsDatabase`. Frequency lists are ordered by their count,
+/// most frequent first.
+#[derive(Default, Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Statistics {
+    pub entries: usize,
+    pub compositions: usize,
+    /// Amount of entries having a component as direct part of a top level `DestructionForm`, also
+    /// if it is modified. Keyed like the `ComponentIndex`: radicals are folded and unencoded
+    /// components are kept by their number
+    pub components: Vec<(CompositionPart, usize)>,
+    /// Amount of entries having a component after recursively decomposing them, keyed the same
+    /// way as `components`
+    pub recursive_components: Vec<(CompositionPart, usize)>,
+    /// How often each `DestructionForm` is used within all compositions
+    pub forms: Vec<(DestructionForm, usize)>,
+    /// Amount of entries with a composition of each origin, in the canonical origin order
+    pub origins: Vec<(Origin, usize)>,
+    /// Entries whose regional compositions differ after normalizing them
+    pub regional_variants: Vec<char>,
+    /// Amount of unencoded components used within all compositions
+    pub unencoded: usize,
+    /// Amount of modifiers used within all compositions
+    pub modifiers: usize,
+}
+
+impl Statistics {
+    /// Collects the statistics of all entries of `db`
+    pub fn new(db: &IdsDatabase) -> Self {
+        let mut stats = Self {
+            entries: db.len(),
+            ..Default::default()
+        };
+
+        let mut components = HashMap::new();
+        let mut recursive_components = HashMap::new();
+        let mut forms = HashMap::new();
+        let mut origins = [0; Origin::ALL.len()];
+
+        for ids in db.iter() {
+            let mut direct = HashSet::new();
+            let mut entry_origins = OriginSet::new();
+
+            for composition in &ids.compositions {
+                stats.compositions += 1;
+                entry_origins = entry_origins.union(composition.reg_origins);
+
+                for node in composition.tree.iter() {
+                    match node {
+                        IdsNode::Operator(form, _) => *forms.entry(*form).or_insert(0) += 1,
+                        IdsNode::Modified(..) => stats.modifiers += 1,
+                        IdsNode::Leaf(part) if part.is_unencoded_component() => {
+                            stats.unencoded += 1
+                        }
+                        IdsNode::Leaf(part) if part.is_modifier() => stats.modifiers += 1,
+                        IdsNode::Leaf(_) => (),
+                    }
+                }
+
+                // Counted the same way as by the `ComponentIndex`
+                let mut parts = HashMap::new();
+                collect_components(&composition.tree, 0, &mut parts);
+                direct.extend(parts.into_iter().filter(|i| i.1).map(|i| i.0));
+            }
+
+            // Atomic entries are composed of themselves
+            direct.remove(&char_key(ids.literal));
+            for component in direct {
+                *components.entry(component).or_insert(0) += 1;
+            }
+
+            // Keyed the same way as the top level components
+            let mut nested: HashSet<CompositionPart> = db
+                .decompose(ids.literal, DecomposeOptions::default())
+                .map(|tree| tree.leaves().filter_map(component_key).collect())
+                .unwrap_or_default();
+            nested.remove(&char_key(ids.literal));
+            for component in nested {
+                *recursive_components.entry(component).or_insert(0) += 1;
+            }
+
+            for origin in entry_origins.iter() {
+                origins[origin.index()] += 1;
+            }
+
+            if has_regional_variants(ids) {
+                stats.regional_variants.push(ids.literal);
+            }
+        }
+
+        stats.components = by_count(components);
+        stats.recursive_components = by_count(recursive_components);
+        stats.forms = by_count(forms);
+        stats.origins = Origin::ALL.into_iter().zip(origins).collect();
+        stats.regional_variants.sort_unstable();
+        stats
+    }
+
+    /// Returns the statistics with all component frequency lists cut down to the `limit` most
+    /// frequent ones
+    pub fn truncated(&self, limit: usize) -> Self {
+        let mut stats = self.clone();
+        stats.components.truncate(limit);
+        stats.recursive_components.truncate(limit);
+        stats
+    }
+
+    /// Returns the amount of entries having `component` as direct part of a top level
+    /// `DestructionForm`. Variant radicals are counted together, see `map_special_form`
+    #[inline]
+    pub fn component_count(&self, component: char) -> usize {
+        count_of(&self.components, &char_key(component))
+    }
+
+    /// Returns the amount of entries having `part` as direct part of a top level
+    /// `DestructionForm`. Unlike `component_count` this also counts unencoded components unknown
+    /// to the built in `PuaMap`.
+    #[inline]
+    pub fn part_count(&self, part: CompositionPart) -> usize {
+        component_key(&part).map_or(0, |i| count_of(&self.components, &i))
+    }
+
+    /// Returns the amount of entries having `component` after recursively decomposing them.
+    /// Variant radicals are counted together, see `map_special_form`
+    #[inline]
+    pub fn recursive_component_count(&self, component: char) -> usize {
+        count_of(&self.recursive_components, &char_key(component))
+    }
+
+    /// Returns the amount of entries having `part` after recursively decomposing them. See
+    /// `part_count`
+    #[inline]
+    pub fn recursive_part_count(&self, part: CompositionPart) -> usize {
+        component_key(&part).map_or(0, |i| count_of(&self.recursive_components, &i))
+    }
+
+    /// Returns how often `form` is used within all compositions
+    #[inline]
+    pub fn form_count(&self, form: DestructionForm) -> usize {
+        count_of(&self.forms, &form)
+    }
+
+    /// Returns the amount of entries with a composition of `origin`
+    #[inline]
+    pub fn origin_count(&self, origin: Origin) -> usize {
+        count_of(&self.origins, &origin)
+    }
+}
+
+impl IdsDatabase {
+    /// Collects the `Statistics` of all entries
+    #[inline]
+    pub fn statistics(&self) -> Statistics {
+        Statistics::new(self)
+    }
+}
+
+/// Formats the statistics as a human readable report
+impl Display for Statistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "entries: {}", self.entries)?;
+        writeln!(f, "compositions: {}", self.compositions)?;
+        writeln!(f, "regional variants: {}", self.regional_variants.len())?;
+        writeln!(f, "unencoded components: {}", self.unencoded)?;
+        writeln!(f, "modifiers: {}", self.modifiers)?;
+
+        writeln!(f, "\norigins:")?;
+        for (origin, count) in &self.origins {
+            writeln!(f, "  {:<7} {count}", origin.code())?;
+        }
+
+        writeln!(f, "\nforms:")?;
+        for (form, count) in &self.forms {
+            writeln!(f, "  {form} {count}")?;
+        }
+
+        writeln!(f, "\ncomponents:")?;
+        for (component, count) in &self.components {
+            writeln!(f, "  {component} {count}")?;
+        }
+
+        writeln!(f, "\nrecursive components:")?;
+        for (component, count) in &self.recursive_components {
+            writeln!(f, "  {component} {count}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns `true` if at least two compositions with a regional source differ after normalizing
+/// them. Alternative compositions and unifiable variants aren't regional.
+fn has_regional_variants(ids: &IDS) -> bool {
+    let non_regional = OriginSet::from([Origin::Alternative, Origin::UnifiableVariant]);
+    let mut trees = ids
+        .compositions
+        .iter()
+        .filter(|i| !(i.reg_origins - non_regional).is_empty())
+        .map(|i| i.tree.normalized());
+
+    match trees.next() {
+        Some(first) => trees.any(|i| i != first),
+        None => false,
+    }
+}
+
+/// Orders counts by their frequency, most frequent first. Equal counts are ordered by the item
+fn by_count<T: Ord + Hash>(counts: HashMap<T, usize>) -> Vec<(T, usize)> {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+fn count_of<T: PartialEq>(counts: &[(T, usize)], item: &T) -> usize {
+    counts
+        .iter()
+        .find(|i| i.0 == *item)
+        .map(|i| i.1)
+        .unwrap_or_default()
+}
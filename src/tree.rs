@@ -0,0 +1,266 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    composition::{CompositionPart, Modifier},
+    destr_form::DestructionForm,
+    error::{ErrorKind, ParseError},
+    radical_map::{Placement, RadicalMap},
+};
+
+/// A single node of the tree representation of a composition. "⿳亠口冋" becomes an
+/// `Operator` with three `Leaf` children.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IdsNode {
+    /// A `DestructionForm` combining as many sub trees as its arity
+    Operator(DestructionForm, Vec<IdsNode>),
+    /// A `Modifier` applied to a single sub tree
+    Modified(Modifier, Box<IdsNode>),
+    /// A radical, an unencoded component or an unrepresentable component
+    Leaf(CompositionPart),
+}
+
+impl Default for IdsNode {
+    #[inline]
+    fn default() -> Self {
+        IdsNode::Leaf(CompositionPart::Modifier(
+            Modifier::UnrepresntableCompontent,
+        ))
+    }
+}
+
+impl IdsNode {
+    /// Builds a tree out of a flat list of `CompositionPart`s in prefix order. Fails if an
+    /// operator or modifier doesn't get the amount of operands it requires. The column of the
+    /// returned error is the index of the part at which parsing failed.
+    pub fn from_parts(parts: &[CompositionPart]) -> Result<IdsNode, ParseError> {
+        let mut pos = 0;
+        let root = parse_node(parts, &mut pos, ErrorKind::EmptyComposition)
+            .map_err(|kind| ParseError::new(kind).at(pos))?;
+        if pos != parts.len() {
+            let err = ErrorKind::TrailingComponents(parts.len() - pos);
+            return Err(ParseError::new(err).at(pos));
+        }
+        Ok(root)
+    }
+
+    /// Returns the `CompositionPart` represented by this node itself
+    #[inline]
+    pub fn part(&self) -> CompositionPart {
+        match self {
+            IdsNode::Operator(form, _) => CompositionPart::Destructive(*form),
+            IdsNode::Modified(modifier, _) => CompositionPart::Modifier(*modifier),
+            IdsNode::Leaf(part) => *part,
+        }
+    }
+
+    /// Returns the direct children of the node
+    #[inline]
+    pub fn children(&self) -> &[IdsNode] {
+        match self {
+            IdsNode::Operator(_, children) => children,
+            IdsNode::Modified(_, child) => std::slice::from_ref(child.as_ref()),
+            IdsNode::Leaf(_) => &[],
+        }
+    }
+
+    /// Returns `true` if the node is a [`Leaf`].
+    ///
+    /// [`Leaf`]: IdsNode::Leaf
+    #[inline]
+    pub fn is_leaf(&self) -> bool {
+        matches!(self, Self::Leaf(..))
+    }
+
+    /// Returns an iterator over all nodes of the tree in prefix order
+    #[inline]
+    pub fn iter(&self) -> NodeIter<'_> {
+        NodeIter { stack: vec![self] }
+    }
+
+    /// Returns an iterator over all leaves of the tree, from left to right
+    #[inline]
+    pub fn leaves(&self) -> impl Iterator<Item = &CompositionPart> {
+        self.iter().filter_map(|i| match i {
+            IdsNode::Leaf(part) => Some(part),
+            _ => None,
+        })
+    }
+
+    /// Returns the tree in its canonical form, so that equivalent trees compare equal. Chains
+    /// of the same direction (eg. ⿲abc, ⿰a⿰bc and ⿰⿰abc) become right nested binary forms
+    /// (⿰a⿰bc) and radical variants get folded using the built in `RadicalMap`.
+    #[inline]
+    pub fn normalized(&self) -> IdsNode {
+        self.normalized_with(RadicalMap::standard())
+    }
+
+    /// Like `normalized` but folds radical variants with `map`, taking their placement within
+    /// the whole chain of the same direction into account. Pass an empty `RadicalMap` to disable
+    /// folding.
+    #[inline]
+    pub fn normalized_with(&self, map: &RadicalMap) -> IdsNode {
+        self.flattened().folded(map, Placement::Any).restructured()
+    }
+
+    /// Merges chains of the same direction (eg. ⿲abc, ⿰a⿰bc and ⿰⿰abc) into a single binary
+    /// form operator holding all items of the chain
+    fn flattened(&self) -> IdsNode {
+        match self {
+            IdsNode::Operator(form, children) => {
+                let children = children.iter().map(|i| i.flattened());
+                let Some(binary) = chain_form(*form) else {
+                    return IdsNode::Operator(*form, children.collect());
+                };
+
+                let mut items = vec![];
+                for child in children {
+                    match child {
+                        IdsNode::Operator(f, grand_children) if f == binary => {
+                            items.extend(grand_children)
+                        }
+                        child => items.push(child),
+                    }
+                }
+                IdsNode::Operator(binary, items)
+            }
+            IdsNode::Modified(modifier, child) => {
+                IdsNode::Modified(*modifier, Box::new(child.flattened()))
+            }
+            IdsNode::Leaf(part) => IdsNode::Leaf(*part),
+        }
+    }
+
+    /// Rebuilds flattened chains as right nested binary forms
+    fn restructured(&self) -> IdsNode {
+        match self {
+            IdsNode::Operator(form, children) => {
+                let children = children.iter().map(|i| i.restructured());
+                if chain_form(*form).is_none() {
+                    return IdsNode::Operator(*form, children.collect());
+                }
+
+                let mut items = children.rev();
+                let last = items.next().unwrap_or_default();
+                items.fold(last, |acc, item| IdsNode::Operator(*form, vec![item, acc]))
+            }
+            IdsNode::Modified(modifier, child) => {
+                IdsNode::Modified(*modifier, Box::new(child.restructured()))
+            }
+            IdsNode::Leaf(part) => IdsNode::Leaf(*part),
+        }
+    }
+
+    /// Folds all radicals with `map`. `placement` is the placement of the node itself
+    fn folded(&self, map: &RadicalMap, placement: Placement) -> IdsNode {
+        match self {
+            IdsNode::Operator(form, children) => {
+                let children = children
+                    .iter()
+                    .enumerate()
+                    .map(|(i, child)| child.folded(map, chain_placement(*form, i, children.len())))
+                    .collect();
+                IdsNode::Operator(*form, children)
+            }
+            IdsNode::Modified(modifier, child) => {
+                IdsNode::Modified(*modifier, Box::new(child.folded(map, placement)))
+            }
+            IdsNode::Leaf(part) => IdsNode::Leaf(part.folded(map, placement)),
+        }
+    }
+
+    /// Converts the tree back into the flat prefix ordered representation
+    #[inline]
+    pub fn to_parts(&self) -> Vec<CompositionPart> {
+        self.iter().map(|i| i.part()).collect()
+    }
+}
+
+/// Prefix order iterator over the nodes of an `IdsNode`
+pub struct NodeIter<'a> {
+    stack: Vec<&'a IdsNode>,
+}
+
+impl<'a> Iterator for NodeIter<'a> {
+    type Item = &'a IdsNode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.children().iter().rev());
+        Some(node)
+    }
+}
+
+/// Returns the binary form of a `DestructionForm` which can be chained, eg. ⿰ for ⿲
+fn chain_form(form: DestructionForm) -> Option<DestructionForm> {
+    match form {
+        DestructionForm::Vertically | DestructionForm::Vertically3 => {
+            Some(DestructionForm::Vertically)
+        }
+        DestructionForm::Horizontally | DestructionForm::Horizontally3 => {
+            Some(DestructionForm::Horizontally)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the placement of the `index`th of `len` children of `form`. Within flattened chains
+/// only the first and the last item are placed at an edge.
+fn chain_placement(form: DestructionForm, index: usize, len: usize) -> Placement {
+    match chain_form(form) {
+        Some(binary) if index > 0 && index + 1 == len => Placement::of(binary, 1),
+        Some(_) if index > 0 => Placement::Any,
+        _ => Placement::of(form, index),
+    }
+}
+
+/// Formats the tree in its prefix ordered form, eg. ⿱⿹𠃌㐅一
+impl Display for IdsNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for node in self.iter() {
+            write!(f, "{}", node.part())?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&[CompositionPart]> for IdsNode {
+    type Error = ParseError;
+
+    #[inline]
+    fn try_from(parts: &[CompositionPart]) -> Result<Self, Self::Error> {
+        IdsNode::from_parts(parts)
+    }
+}
+
+impl From<&IdsNode> for Vec<CompositionPart> {
+    #[inline]
+    fn from(node: &IdsNode) -> Self {
+        node.to_parts()
+    }
+}
+
+/// Parses the node at `pos`. Returns `missing` if there is no part left.
+fn parse_node(
+    parts: &[CompositionPart],
+    pos: &mut usize,
+    missing: ErrorKind,
+) -> Result<IdsNode, ErrorKind> {
+    let part = *parts.get(*pos).ok_or(missing)?;
+    *pos += 1;
+
+    Ok(match part {
+        CompositionPart::Destructive(form) => {
+            let children = (0..form.arity())
+                .map(|_| parse_node(parts, pos, ErrorKind::MissingOperand(form)))
+                .collect::<Result<Vec<_>, _>>()?;
+            IdsNode::Operator(form, children)
+        }
+        CompositionPart::Modifier(modifier) if modifier.arity() == 1 => {
+            let child = parse_node(parts, pos, ErrorKind::DanglingModifier(modifier))?;
+            IdsNode::Modified(modifier, Box::new(child))
+        }
+        _ => IdsNode::Leaf(part),
+    })
+}
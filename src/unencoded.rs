@@ -0,0 +1,224 @@
+use std::{collections::BTreeMap, fs::File, io::Read, path::Path, str::FromStr, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    composition::CompositionPart,
+    error::{ErrorKind, LoadError, ParseError},
+    pua_map::PuaMap,
+    utils,
+};
+
+/// An unencoded component written as `{NN}` in a composition
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct UnencodedComponent {
+    /// The number used within compositions
+    pub number: u32,
+    /// The Private Use Area character a font displays the component with
+    pub code_point: Option<char>,
+    /// An approximate composition of the component, eg. "⿱亠口"
+    pub approx_ids: Option<String>,
+    /// Textual description of what the component looks like
+    pub description: Option<String>,
+    pub strokes: Option<u8>,
+}
+
+/// Metadata of all known unencoded components, ordered by their number
+#[derive(Default, Clone, Debug)]
+pub struct UnencodedTable {
+    components: BTreeMap<u32, UnencodedComponent>,
+}
+
+impl UnencodedComponent {
+    /// Creates a new `UnencodedComponent` without any metadata besides its built in PUA code point
+    #[inline]
+    pub fn new(number: u32) -> Self {
+        Self {
+            number,
+            code_point: PuaMap::builtin().lookup(number),
+            approx_ids: None,
+            description: None,
+            strokes: None,
+        }
+    }
+
+    /// Returns something displayable for the component: its PUA character, its approximate
+    /// composition or its number as written in compositions
+    pub fn display_text(&self) -> String {
+        if let Some(c) = self.code_point {
+            return c.to_string();
+        }
+        if let Some(ids) = &self.approx_ids {
+            return ids.clone();
+        }
+        format!("{{{:02}}}", self.number)
+    }
+}
+
+impl UnencodedTable {
+    /// Creates a new and empty `UnencodedTable`
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the built in side table of all unencoded components of IDS.TXT, shipped as
+    /// data/unencoded.txt
+    pub fn builtin() -> Self {
+        static BUILTIN: OnceLock<UnencodedTable> = OnceLock::new();
+        BUILTIN
+            .get_or_init(|| {
+                let table = include_str!("../data/unencoded.txt");
+                table.parse().expect("valid built in side table")
+            })
+            .clone()
+    }
+
+    /// Loads the side table at `path`
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        Self::from_reader(File::open(path)?)
+    }
+
+    /// Loads a side table from `reader`. See the `FromStr` implementation for the format.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, LoadError> {
+        Ok(utils::read_table(reader, parse_line)?.into_iter().collect())
+    }
+
+    /// Adds a component. Metadata of an existing component with the same number gets replaced,
+    /// except for fields missing in `component`.
+    pub fn insert(&mut self, component: UnencodedComponent) {
+        match self.components.get_mut(&component.number) {
+            Some(existing) => {
+                let UnencodedComponent {
+                    code_point,
+                    approx_ids,
+                    description,
+                    strokes,
+                    ..
+                } = component;
+                existing.code_point = code_point.or(existing.code_point);
+                existing.approx_ids = approx_ids.or(existing.approx_ids.take());
+                existing.description = description.or(existing.description.take());
+                existing.strokes = strokes.or(existing.strokes);
+            }
+            None => {
+                self.components.insert(component.number, component);
+            }
+        }
+    }
+
+    /// Adds all components of `other`. See `insert`
+    #[inline]
+    pub fn extend(&mut self, other: UnencodedTable) {
+        for component in other.components.into_values() {
+            self.insert(component);
+        }
+    }
+
+    /// Returns the metadata of the component with the given number
+    #[inline]
+    pub fn get(&self, number: u32) -> Option<&UnencodedComponent> {
+        self.components.get(&number)
+    }
+
+    /// Returns the metadata of `part` if it is an unencoded component
+    #[inline]
+    pub fn get_part(&self, part: &CompositionPart) -> Option<&UnencodedComponent> {
+        match part {
+            CompositionPart::UnencodedComponent(nr) => self.get(*nr),
+            _ => None,
+        }
+    }
+
+    /// Returns the component displayed with the PUA character `c`
+    #[inline]
+    pub fn by_code_point(&self, c: char) -> Option<&UnencodedComponent> {
+        self.iter().find(|i| i.code_point == Some(c))
+    }
+
+    /// Returns an iterator over all components ordered by their number
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &UnencodedComponent> {
+        self.components.values()
+    }
+
+    /// Returns the amount of components
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    /// Returns `true` if there are no components
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+}
+
+impl FromIterator<UnencodedComponent> for UnencodedTable {
+    fn from_iter<T: IntoIterator<Item = UnencodedComponent>>(iter: T) -> Self {
+        let mut table = Self::new();
+        for component in iter {
+            table.insert(component);
+        }
+        table
+    }
+}
+
+/// Parses a side table with one tab separated component per line of the format
+/// "{NN}\tU+XXXX\tstrokes\tapprox ids\tdescription". All fields but the number may be empty or
+/// missing. Empty lines and lines starting with '#' get skipped.
+impl FromStr for UnencodedTable {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(utils::parse_table_str(s, parse_line)?.into_iter().collect())
+    }
+}
+
+/// Parses a single line of a side table
+fn parse_line(line: &str) -> Result<UnencodedComponent, ParseError> {
+    let line = line.trim_end();
+
+    // All fields together with their column
+    let mut fields = vec![];
+    let mut column = 0;
+    for field in line.split('\t') {
+        fields.push((column, field.trim()));
+        column += field.chars().count() + 1;
+    }
+    let field = |i: usize| fields.get(i).filter(|i| !i.1.is_empty());
+    let err = |column: usize| ParseError::new(ErrorKind::UnexpectedCharacter).at(column);
+
+    let number = fields[0]
+        .1
+        .strip_prefix('{')
+        .and_then(|i| i.strip_suffix('}'))
+        .and_then(|i| i.parse().ok())
+        .ok_or_else(|| err(0))?;
+
+    let mut component = UnencodedComponent {
+        number,
+        code_point: None,
+        approx_ids: None,
+        description: None,
+        strokes: None,
+    };
+
+    if let Some((column, code)) = field(1) {
+        let c = utils::utf_code_to_char(code)
+            .ok_or_else(|| ParseError::new(ErrorKind::InvalidCodePoint).at(*column))?;
+        component.code_point = Some(c);
+    }
+    if let Some((column, strokes)) = field(2) {
+        component.strokes = Some(strokes.parse().map_err(|_| err(*column))?);
+    }
+    component.approx_ids = field(3).map(|i| i.1.to_string());
+    component.description = field(4).map(|i| i.1.to_string());
+
+    if let Some((column, _)) = fields.get(5) {
+        return Err(ParseError::new(ErrorKind::TrailingComponents(fields.len() - 5)).at(*column));
+    }
+
+    Ok(component)
+}
@@ -1,5 +1,77 @@
+use std::{
+    collections::HashSet,
+    io::{BufRead, BufReader, Read},
+};
+
+use crate::error::{LoadError, ParseError};
+
 /// Converts an String encoded utf8-string (eg: `U+9AD8`) to the representing character
 #[inline]
 pub fn utf_code_to_char(code: &str) -> Option<char> {
     char::from_u32(u32::from_str_radix(code.split('+').nth(1)?, 16).ok()?)
 }
+
+/// Converts a character to its String encoded utf8-string (eg: `U+9AD8`)
+#[inline]
+pub fn char_to_utf_code(c: char) -> String {
+    format!("U+{:04X}", c as u32)
+}
+
+/// Returns `true` if `c` is a variation selector, which may follow the literal of an entry
+#[inline]
+pub(crate) fn is_variation_selector(c: char) -> bool {
+    matches!(c, '\u{FE00}'..='\u{FE0F}' | '\u{E0100}'..='\u{E01EF}')
+}
+
+/// Collects the items of `set` into a sorted `Vec`
+#[inline]
+pub(crate) fn sorted<T: Ord>(set: HashSet<T>) -> Vec<T> {
+    let mut out: Vec<T> = set.into_iter().collect();
+    out.sort_unstable();
+    out
+}
+
+/// Parses a side table from `s` with one item per line. See `parse_table`
+#[inline]
+pub(crate) fn parse_table_str<T>(
+    s: &str,
+    parse_line: impl Fn(&str) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    parse_table(s.lines().map(Ok), parse_line)
+}
+
+/// Loads a side table from `reader` with one item per line. See `parse_table`
+#[inline]
+pub(crate) fn read_table<R: Read, T>(
+    reader: R,
+    parse_line: impl Fn(&str) -> Result<T, ParseError>,
+) -> Result<Vec<T>, LoadError> {
+    parse_table(
+        BufReader::new(reader)
+            .lines()
+            .map(|i| i.map_err(LoadError::from)),
+        parse_line,
+    )
+}
+
+/// Parses each line of a side table with `parse_line`. A leading byte order mark gets removed,
+/// empty lines and lines starting with '#' get skipped. Errors carry the line number.
+fn parse_table<L, E, T>(
+    lines: impl Iterator<Item = Result<L, E>>,
+    parse_line: impl Fn(&str) -> Result<T, ParseError>,
+) -> Result<Vec<T>, E>
+where
+    L: AsRef<str>,
+    E: From<ParseError>,
+{
+    let mut items = vec![];
+    for (nr, line) in lines.enumerate() {
+        let line = line?;
+        let line = line.as_ref().trim_start_matches('\u{feff}');
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        items.push(parse_line(line).map_err(|e| e.on_line(nr + 1))?);
+    }
+    Ok(items)
+}
@@ -0,0 +1,167 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::Write,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::IdsDatabase,
+    utils,
+    xref::{RefType, XRef},
+};
+
+/// Undirected graph of all characters connected by cross references
+#[derive(Default, Clone, Debug)]
+pub struct VariantGraph {
+    adjacency: HashMap<char, Vec<(char, RefType)>>,
+    edges: BTreeSet<VariantEdge>,
+}
+
+/// A single edge of a `VariantGraph`. `left` is always the smaller character.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct VariantEdge {
+    pub left: char,
+    pub right: char,
+    pub ref_type: RefType,
+}
+
+impl VariantGraph {
+    /// Builds the graph out of the cross references of all entries of `db`
+    pub fn new(db: &IdsDatabase) -> Self {
+        let mut graph = Self::default();
+        for xref in db.iter().flat_map(|i| i.xrefs.iter()) {
+            graph.add_xref(xref);
+        }
+        graph
+    }
+
+    /// Adds all relations of `xref` to the graph
+    pub fn add_xref(&mut self, xref: &XRef) {
+        for (left, ref_type, right) in xref.pairs() {
+            self.add_edge(left.literal, right.literal, ref_type);
+        }
+    }
+
+    /// Adds a single relation to the graph
+    pub fn add_edge(&mut self, a: char, b: char, ref_type: RefType) {
+        if a == b {
+            return;
+        }
+
+        let edge = VariantEdge {
+            left: a.min(b),
+            right: a.max(b),
+            ref_type,
+        };
+        if !self.edges.insert(edge) {
+            return;
+        }
+
+        self.adjacency.entry(a).or_default().push((b, ref_type));
+        self.adjacency.entry(b).or_default().push((a, ref_type));
+    }
+
+    /// Returns all direct neighbours of `c` together with their relation
+    #[inline]
+    pub fn neighbours(&self, c: char) -> &[(char, RefType)] {
+        self.adjacency
+            .get(&c)
+            .map(|i| i.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Returns all characters which are unifiable variants of `c`, directly or transitively
+    #[inline]
+    pub fn unifiable_variants(&self, c: char) -> Vec<char> {
+        self.reachable_without(c, |i| i == RefType::UnifiableVariants)
+    }
+
+    /// Returns all characters which have identical glyphs to `c`, directly or transitively
+    #[inline]
+    pub fn identical_glyphs(&self, c: char) -> Vec<char> {
+        self.reachable_without(c, |i| i == RefType::Full)
+    }
+
+    /// Returns all characters connected to `c` by any relation, including `c` itself
+    #[inline]
+    pub fn component(&self, c: char) -> Vec<char> {
+        utils::sorted(self.reachable(c, |_| true))
+    }
+
+    /// Returns all connected components of the graph, each ordered by code point
+    pub fn components(&self) -> Vec<Vec<char>> {
+        let mut seen = HashSet::new();
+        let mut components = vec![];
+
+        let mut nodes: Vec<char> = self.adjacency.keys().copied().collect();
+        nodes.sort_unstable();
+
+        for node in nodes {
+            if seen.contains(&node) {
+                continue;
+            }
+            let component = self.reachable(node, |_| true);
+            seen.extend(component.iter().copied());
+            components.push(utils::sorted(component));
+        }
+
+        components
+    }
+
+    /// Returns all edges of the graph
+    #[inline]
+    pub fn edges(&self) -> impl Iterator<Item = &VariantEdge> {
+        self.edges.iter()
+    }
+
+    /// Returns the graph in the Graphviz DOT format
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("graph variants {\n");
+        for edge in &self.edges {
+            let _ = writeln!(
+                out,
+                "    \"{}\" -- \"{}\" [label=\"{}\"];",
+                edge.left, edge.right, edge.ref_type
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Returns `true` if the graph has no edges
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    fn reachable_without<F: Fn(RefType) -> bool>(&self, c: char, follow: F) -> Vec<char> {
+        let mut reachable = self.reachable(c, follow);
+        reachable.remove(&c);
+        utils::sorted(reachable)
+    }
+
+    /// Returns all characters reachable from `c` over edges matching `follow`
+    fn reachable<F: Fn(RefType) -> bool>(&self, c: char, follow: F) -> HashSet<char> {
+        let mut seen = HashSet::from([c]);
+        let mut queue = VecDeque::from([c]);
+
+        while let Some(next) = queue.pop_front() {
+            for (neighbour, ref_type) in self.neighbours(next) {
+                if follow(*ref_type) && seen.insert(*neighbour) {
+                    queue.push_back(*neighbour);
+                }
+            }
+        }
+
+        seen
+    }
+}
+
+impl IdsDatabase {
+    /// Builds a `VariantGraph` over the cross references of all entries
+    #[inline]
+    pub fn variant_graph(&self) -> VariantGraph {
+        VariantGraph::new(self)
+    }
+}
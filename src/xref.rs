@@ -1,12 +1,25 @@
-use crate::{error::ParseError, utils, Origin};
+use crate::{
+    error::{ErrorKind, Field, ParseError},
+    utils, Origin,
+};
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use std::{fmt::Display, str::FromStr};
 
+/// A chain of cross references like 'U+4E8C≠U+2011E≠U+2011F'
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct XRef {
+    /// The first item of the chain
+    pub head: XRefItem,
+    /// All following items together with their relation to the previous item
+    pub links: Vec<XRefLink>,
+}
+
+/// A single link of an `XRef` chain
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct XRefLink {
+    /// Relation between the previous item and `item`
     pub ref_type: RefType,
-    pub left: XRefItem,
-    pub right: XRefItem,
+    pub item: XRefItem,
 }
 
 #[derive(Default, Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -16,7 +29,7 @@ pub struct XRefItem {
 }
 
 /// Type of cross reference
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum RefType {
     /// Glyphs are the sams for different characters
     Full,
@@ -26,37 +39,65 @@ pub enum RefType {
     UnifiableVariants,
 }
 
+impl XRef {
+    /// Returns an iterator over all items of the chain in order
+    #[inline]
+    pub fn items(&self) -> impl Iterator<Item = &XRefItem> {
+        std::iter::once(&self.head).chain(self.links.iter().map(|i| &i.item))
+    }
+
+    /// Returns an iterator over all pairs of neighbouring items together with their relation
+    #[inline]
+    pub fn pairs(&self) -> impl Iterator<Item = (&XRefItem, RefType, &XRefItem)> {
+        self.items()
+            .zip(self.links.iter())
+            .map(|(left, link)| (left, link.ref_type, &link.item))
+    }
+}
+
 impl FromStr for XRef {
     type Err = ParseError;
 
-    #[inline]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Parses the item starting at `column`
+        let parse_item = |item: &str, column: usize| {
+            XRefItem::from_str(item).map_err(|e| e.shifted(column).in_field(Field::XRef, s))
+        };
+
+        let mut head = None;
+        let mut links = vec![];
+
         let mut ref_type: Option<RefType> = None;
-        let mut ref_char = ' ';
-        for next in s.chars() {
-            if let Ok(rt) = RefType::try_from(next) {
-                ref_type = Some(rt);
-                ref_char = next;
-                break;
+        let mut item_start = (0, 0);
+
+        for (column, (pos, c)) in s.char_indices().enumerate() {
+            let Ok(next_type) = RefType::try_from(c) else {
+                continue;
+            };
+
+            let item = parse_item(&s[item_start.0..pos], item_start.1)?;
+            match ref_type {
+                Some(ref_type) => links.push(XRefLink { ref_type, item }),
+                None => head = Some(item),
             }
-        }
 
-        if ref_type.is_none() {
-            return Err(ParseError::InvalidXRef)?;
+            ref_type = Some(next_type);
+            item_start = (pos + c.len_utf8(), column + 1);
         }
 
-        let mut s_iter = s.split(ref_char);
-        let left_str = s_iter.next().ok_or(ParseError::InvalidXRef)?;
-        let right_str = s_iter.next().ok_or(ParseError::InvalidXRef)?;
+        let (head, ref_type) = match (head, ref_type) {
+            (Some(head), Some(ref_type)) => (head, ref_type),
+            _ => {
+                return Err(ParseError::new(ErrorKind::InvalidXRef)
+                    .at(0)
+                    .in_field(Field::XRef, s))
+            }
+        };
 
-        let left = XRefItem::from_str(left_str)?;
-        let right = XRefItem::from_str(right_str)?;
+        let item = parse_item(&s[item_start.0..], item_start.1)?;
+        links.push(XRefLink { ref_type, item });
 
-        Ok(XRef {
-            ref_type: ref_type.unwrap(),
-            left,
-            right,
-        })
+        Ok(XRef { head, links })
     }
 }
 
@@ -69,7 +110,7 @@ impl TryFrom<char> for RefType {
             '=' => RefType::Full,
             '≠' => RefType::FullNoCognate,
             '≡' => RefType::UnifiableVariants,
-            _ => return Err(ParseError::InvalidRefType(value)),
+            _ => return Err(ErrorKind::InvalidRefType(value).into()),
         })
     }
 }
@@ -79,18 +120,23 @@ impl FromStr for XRefItem {
 
     // Takes something like 'U+5098(V)'
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split('(');
+        let (utf8_str, origin_str) = match s.split_once('(') {
+            Some((code, rest)) => (code, Some(rest)),
+            None => (s, None),
+        };
 
-        let utf8_str = parts.next().ok_or(ParseError::InvalidXRef)?;
-        let character = utils::utf_code_to_char(utf8_str).ok_or(ParseError::InvalidXRef)?;
+        let err = |column| ParseError::new(ErrorKind::InvalidXRef).at(column);
+        let character = utils::utf_code_to_char(utf8_str).ok_or_else(|| err(0))?;
 
         let mut origin = None;
 
-        if let Some(next) = parts.next() {
-            if next.len() > 1 && next.ends_with(')') {
-                let origin_char = next.chars().next().unwrap();
-                origin = Some(Origin::try_from(origin_char)?);
-            }
+        if let Some(origin_str) = origin_str {
+            let column = utf8_str.chars().count() + 1;
+            let code = origin_str
+                .strip_suffix(')')
+                .filter(|i| !i.is_empty())
+                .ok_or_else(|| err(column - 1))?;
+            origin = Some(Origin::from_str(code).map_err(|e| e.at(column))?);
         }
 
         Ok(XRefItem {
@@ -99,3 +145,43 @@ impl FromStr for XRefItem {
         })
     }
 }
+
+impl From<RefType> for char {
+    #[inline]
+    fn from(ref_type: RefType) -> Self {
+        match ref_type {
+            RefType::Full => '=',
+            RefType::FullNoCognate => '≠',
+            RefType::UnifiableVariants => '≡',
+        }
+    }
+}
+
+impl Display for RefType {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", char::from(*self))
+    }
+}
+
+/// Formats the cross reference like 'U+507D≡U+50DE'
+impl Display for XRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.head)?;
+        for link in &self.links {
+            write!(f, "{}{}", link.ref_type, link.item)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats the item like 'U+5098(V)'
+impl Display for XRefItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", utils::char_to_utf_code(self.literal))?;
+        if let Some(origin) = self.src_identifier {
+            write!(f, "({origin})")?;
+        }
+        Ok(())
+    }
+}